@@ -0,0 +1,225 @@
+-- Scallop events, one table per event type.
+-- Addresses and object ids are 0x prefixed hex, u64 amounts are NUMERIC(20), TypeName is its name.
+
+CREATE TABLE watermarks (
+    name TEXT PRIMARY KEY,
+    checkpoint BIGINT NOT NULL
+);
+
+-- every indexed event, decoded is NULL for dead letters
+CREATE TABLE events (
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    type TEXT NOT NULL,
+    data BYTEA NOT NULL,
+    decoded JSONB,
+    obligation TEXT,
+    error TEXT,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX events_type_idx ON events (type, checkpoint);
+CREATE INDEX events_obligation_idx ON events (obligation) WHERE obligation IS NOT NULL;
+CREATE INDEX events_error_idx ON events (checkpoint) WHERE error IS NOT NULL;
+
+CREATE TABLE borrow_events (
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    borrower TEXT NOT NULL,
+    obligation TEXT NOT NULL,
+    asset TEXT NOT NULL,
+    amount NUMERIC(20) NOT NULL,
+    time NUMERIC(20) NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX borrow_events_epoch_idx ON borrow_events (epoch);
+CREATE INDEX borrow_events_obligation_idx ON borrow_events (obligation);
+
+CREATE TABLE borrow_events_v2 (
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    borrower TEXT NOT NULL,
+    obligation TEXT NOT NULL,
+    asset TEXT NOT NULL,
+    amount NUMERIC(20) NOT NULL,
+    borrow_fee NUMERIC(20) NOT NULL,
+    time NUMERIC(20) NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX borrow_events_v2_epoch_idx ON borrow_events_v2 (epoch);
+CREATE INDEX borrow_events_v2_obligation_idx ON borrow_events_v2 (obligation);
+
+CREATE TABLE borrow_flash_loan_events (
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    borrower TEXT NOT NULL,
+    asset TEXT NOT NULL,
+    amount NUMERIC(20) NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX borrow_flash_loan_events_epoch_idx ON borrow_flash_loan_events (epoch);
+
+CREATE TABLE collateral_deposit_events (
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    provider TEXT NOT NULL,
+    obligation TEXT NOT NULL,
+    deposit_asset TEXT NOT NULL,
+    deposit_amount NUMERIC(20) NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX collateral_deposit_events_epoch_idx ON collateral_deposit_events (epoch);
+CREATE INDEX collateral_deposit_events_obligation_idx ON collateral_deposit_events (obligation);
+
+CREATE TABLE collateral_withdraw_events (
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    taker TEXT NOT NULL,
+    obligation TEXT NOT NULL,
+    withdraw_asset TEXT NOT NULL,
+    withdraw_amount NUMERIC(20) NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX collateral_withdraw_events_epoch_idx ON collateral_withdraw_events (epoch);
+CREATE INDEX collateral_withdraw_events_obligation_idx ON collateral_withdraw_events (obligation);
+
+CREATE TABLE liquidate_events (
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    liquidator TEXT NOT NULL,
+    obligation TEXT NOT NULL,
+    debt_type TEXT NOT NULL,
+    collateral_type TEXT NOT NULL,
+    repay_on_behalf NUMERIC(20) NOT NULL,
+    repay_revenue NUMERIC(20) NOT NULL,
+    liq_amount NUMERIC(20) NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX liquidate_events_epoch_idx ON liquidate_events (epoch);
+CREATE INDEX liquidate_events_obligation_idx ON liquidate_events (obligation);
+
+CREATE TABLE mint_events (
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    minter TEXT NOT NULL,
+    deposit_asset TEXT NOT NULL,
+    deposit_amount NUMERIC(20) NOT NULL,
+    mint_asset TEXT NOT NULL,
+    mint_amount NUMERIC(20) NOT NULL,
+    time NUMERIC(20) NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX mint_events_epoch_idx ON mint_events (epoch);
+
+CREATE TABLE obligation_created_events (
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    sender TEXT NOT NULL,
+    obligation TEXT NOT NULL,
+    obligation_key TEXT NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX obligation_created_events_epoch_idx ON obligation_created_events (epoch);
+CREATE INDEX obligation_created_events_obligation_idx ON obligation_created_events (obligation);
+
+CREATE TABLE obligation_locked_events (
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    obligation TEXT NOT NULL,
+    witness TEXT NOT NULL,
+    borrow_locked BOOLEAN NOT NULL,
+    repay_locked BOOLEAN NOT NULL,
+    deposit_collateral_locked BOOLEAN NOT NULL,
+    withdraw_collateral_locked BOOLEAN NOT NULL,
+    liquidate_locked BOOLEAN NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX obligation_locked_events_epoch_idx ON obligation_locked_events (epoch);
+CREATE INDEX obligation_locked_events_obligation_idx ON obligation_locked_events (obligation);
+
+CREATE TABLE obligation_unlocked_events (
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    obligation TEXT NOT NULL,
+    witness TEXT NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX obligation_unlocked_events_epoch_idx ON obligation_unlocked_events (epoch);
+CREATE INDEX obligation_unlocked_events_obligation_idx ON obligation_unlocked_events (obligation);
+
+CREATE TABLE redeem_events (
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    redeemer TEXT NOT NULL,
+    withdraw_asset TEXT NOT NULL,
+    withdraw_amount NUMERIC(20) NOT NULL,
+    burn_asset TEXT NOT NULL,
+    burn_amount NUMERIC(20) NOT NULL,
+    time NUMERIC(20) NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX redeem_events_epoch_idx ON redeem_events (epoch);
+
+CREATE TABLE repay_events (
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    repayer TEXT NOT NULL,
+    obligation TEXT NOT NULL,
+    asset TEXT NOT NULL,
+    amount NUMERIC(20) NOT NULL,
+    time NUMERIC(20) NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX repay_events_epoch_idx ON repay_events (epoch);
+CREATE INDEX repay_events_obligation_idx ON repay_events (obligation);
+
+CREATE TABLE repay_flash_loan_events (
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    borrower TEXT NOT NULL,
+    asset TEXT NOT NULL,
+    amount NUMERIC(20) NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX repay_flash_loan_events_epoch_idx ON repay_flash_loan_events (epoch);
@@ -0,0 +1,3 @@
+-- sender of transaction which emitted the event
+ALTER TABLE events ADD COLUMN sender TEXT;
+CREATE INDEX events_sender_idx ON events (sender);
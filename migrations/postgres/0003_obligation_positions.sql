@@ -0,0 +1,16 @@
+-- positions of obligations reconstructed from events, stored only when indexer tracks obligations
+CREATE TABLE obligations (
+    obligation TEXT PRIMARY KEY,
+    -- last checkpoint applied to the position
+    checkpoint BIGINT NOT NULL
+);
+
+-- side is collateral or debt, debt is borrowed principal without accrued interest, zero amounts are not stored
+CREATE TABLE obligation_positions (
+    obligation TEXT NOT NULL,
+    side TEXT NOT NULL,
+    asset TEXT NOT NULL,
+    amount NUMERIC(20) NOT NULL,
+    PRIMARY KEY (obligation, side, asset)
+);
+CREATE INDEX obligation_positions_asset_idx ON obligation_positions (asset, side);
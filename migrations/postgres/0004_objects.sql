@@ -0,0 +1,21 @@
+-- latest version of indexed objects (eg. obligations and their debt / collateral entries)
+CREATE TABLE objects (
+    object_id TEXT PRIMARY KEY,
+    version BIGINT NOT NULL,
+    type TEXT NOT NULL,
+    -- address, parent object id, shared or immutable
+    owner TEXT NOT NULL,
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    deleted BOOLEAN NOT NULL,
+    -- bcs contents, for deleted objects the contents before deletion
+    data BYTEA NOT NULL,
+    decoded JSONB,
+    error TEXT,
+    -- obligation of the object, for debt and collateral entries their asset and amount (debt with accrued interest)
+    obligation TEXT,
+    asset TEXT,
+    amount NUMERIC(20)
+);
+CREATE INDEX objects_type_idx ON objects (type);
+CREATE INDEX objects_obligation_idx ON objects (obligation) WHERE obligation IS NOT NULL;
@@ -0,0 +1,12 @@
+-- oracle price updates, value has `prices::PRICE_DECIMALS` decimals (USD per whole coin)
+CREATE TABLE prices (
+    asset TEXT NOT NULL,
+    checkpoint BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    value NUMERIC(20) NOT NULL,
+    -- seconds since epoch of the oracle update
+    last_updated NUMERIC(20) NOT NULL,
+    PRIMARY KEY (asset, checkpoint)
+);
+-- prices of event assets in effect at its checkpoint, {asset: value}
+ALTER TABLE events ADD COLUMN prices JSONB;
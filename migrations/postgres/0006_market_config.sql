@@ -0,0 +1,88 @@
+-- Scallop market configuration events (interest and risk models of assets).
+-- Fixed point values (rates, kinks, factors) are raw FixedPoint32 values, real value is value / 2^32.
+-- *_added_events are models applied to market (listing of asset or change which became effective),
+-- so parameters in effect at checkpoint are in the latest row of asset at or before it.
+
+CREATE TABLE interest_model_added_events (
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    asset TEXT NOT NULL,
+    base_borrow_rate_per_sec NUMERIC(20) NOT NULL,
+    interest_rate_scale NUMERIC(20) NOT NULL,
+    borrow_rate_on_mid_kink NUMERIC(20) NOT NULL,
+    mid_kink NUMERIC(20) NOT NULL,
+    borrow_rate_on_high_kink NUMERIC(20) NOT NULL,
+    high_kink NUMERIC(20) NOT NULL,
+    max_borrow_rate NUMERIC(20) NOT NULL,
+    revenue_factor NUMERIC(20) NOT NULL,
+    borrow_weight NUMERIC(20) NOT NULL,
+    min_borrow_amount NUMERIC(20) NOT NULL,
+    current_epoch NUMERIC(20) NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX interest_model_added_events_asset_idx ON interest_model_added_events (asset, checkpoint);
+
+CREATE TABLE interest_model_change_created_events (
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    asset TEXT NOT NULL,
+    base_borrow_rate_per_sec NUMERIC(20) NOT NULL,
+    interest_rate_scale NUMERIC(20) NOT NULL,
+    borrow_rate_on_mid_kink NUMERIC(20) NOT NULL,
+    mid_kink NUMERIC(20) NOT NULL,
+    borrow_rate_on_high_kink NUMERIC(20) NOT NULL,
+    high_kink NUMERIC(20) NOT NULL,
+    max_borrow_rate NUMERIC(20) NOT NULL,
+    revenue_factor NUMERIC(20) NOT NULL,
+    borrow_weight NUMERIC(20) NOT NULL,
+    min_borrow_amount NUMERIC(20) NOT NULL,
+    current_epoch NUMERIC(20) NOT NULL,
+    delay_epoches NUMERIC(20) NOT NULL,
+    effective_epoches NUMERIC(20) NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX interest_model_change_created_events_asset_idx ON interest_model_change_created_events (asset, checkpoint);
+
+CREATE TABLE risk_model_added_events (
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    asset TEXT NOT NULL,
+    collateral_factor NUMERIC(20) NOT NULL,
+    liquidation_factor NUMERIC(20) NOT NULL,
+    liquidation_penalty NUMERIC(20) NOT NULL,
+    liquidation_discount NUMERIC(20) NOT NULL,
+    liquidation_revenue_factor NUMERIC(20) NOT NULL,
+    max_collateral_amount NUMERIC(20) NOT NULL,
+    current_epoch NUMERIC(20) NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX risk_model_added_events_asset_idx ON risk_model_added_events (asset, checkpoint);
+
+CREATE TABLE risk_model_change_created_events (
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    asset TEXT NOT NULL,
+    collateral_factor NUMERIC(20) NOT NULL,
+    liquidation_factor NUMERIC(20) NOT NULL,
+    liquidation_penalty NUMERIC(20) NOT NULL,
+    liquidation_discount NUMERIC(20) NOT NULL,
+    liquidation_revenue_factor NUMERIC(20) NOT NULL,
+    max_collateral_amount NUMERIC(20) NOT NULL,
+    current_epoch NUMERIC(20) NOT NULL,
+    delay_epoches NUMERIC(20) NOT NULL,
+    effective_epoches NUMERIC(20) NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX risk_model_change_created_events_asset_idx ON risk_model_change_created_events (asset, checkpoint);
@@ -0,0 +1,55 @@
+-- Scallop borrow / supply limit changes and interest accrual, applied right away (no proposed change).
+CREATE TABLE borrow_limit_changed_events (
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    asset TEXT NOT NULL,
+    borrow_limit NUMERIC(20) NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX borrow_limit_changed_events_asset_idx ON borrow_limit_changed_events (asset, checkpoint);
+
+CREATE TABLE supply_limit_changed_events (
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    asset TEXT NOT NULL,
+    supply_limit NUMERIC(20) NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX supply_limit_changed_events_asset_idx ON supply_limit_changed_events (asset, checkpoint);
+
+-- interest_rate is raw FixedPoint32 per second, last_updated is in seconds
+CREATE TABLE interest_accrued_events (
+    checkpoint BIGINT NOT NULL,
+    digest TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp_ms BIGINT NOT NULL,
+    asset TEXT NOT NULL,
+    borrow_index NUMERIC(20) NOT NULL,
+    interest_rate NUMERIC(20) NOT NULL,
+    interest_rate_scale NUMERIC(20) NOT NULL,
+    last_updated NUMERIC(20) NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX interest_accrued_events_asset_idx ON interest_accrued_events (asset, checkpoint);
+
+-- market configuration of asset in effect since checkpoint (`market::MarketConfig`), written by the pipeline
+-- for each checkpoint which changed it, so configuration at checkpoint is the latest row of asset at or before it.
+-- factors are raw FixedPoint32 values, missing when the model was not added yet.
+CREATE TABLE market_config (
+    asset TEXT NOT NULL,
+    checkpoint BIGINT NOT NULL,
+    config JSONB NOT NULL,
+    collateral_factor NUMERIC(20),
+    liquidation_factor NUMERIC(20),
+    borrow_weight NUMERIC(20),
+    borrow_limit NUMERIC(20),
+    supply_limit NUMERIC(20),
+    PRIMARY KEY (asset, checkpoint)
+);
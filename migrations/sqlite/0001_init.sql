@@ -0,0 +1,227 @@
+-- Same schema as migrations/postgres/0001_init.sql.
+-- Addresses and object ids are 0x prefixed hex, TypeName is its name.
+-- u64 amounts are TEXT with decimal value, INTEGER would store values above i64::MAX as REAL (sqlite has no unsigned or decimal type),
+-- compare them as numbers by CAST(amount AS NUMERIC).
+
+CREATE TABLE watermarks (
+    name TEXT PRIMARY KEY,
+    checkpoint INTEGER NOT NULL
+);
+
+-- every indexed event, decoded is NULL for dead letters
+CREATE TABLE events (
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    epoch INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    type TEXT NOT NULL,
+    data BLOB NOT NULL,
+    decoded TEXT,
+    obligation TEXT,
+    error TEXT,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX events_type_idx ON events (type, checkpoint);
+CREATE INDEX events_obligation_idx ON events (obligation) WHERE obligation IS NOT NULL;
+CREATE INDEX events_error_idx ON events (checkpoint) WHERE error IS NOT NULL;
+
+CREATE TABLE borrow_events (
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    epoch INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    borrower TEXT NOT NULL,
+    obligation TEXT NOT NULL,
+    asset TEXT NOT NULL,
+    amount TEXT NOT NULL,
+    time TEXT NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX borrow_events_epoch_idx ON borrow_events (epoch);
+CREATE INDEX borrow_events_obligation_idx ON borrow_events (obligation);
+
+CREATE TABLE borrow_events_v2 (
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    epoch INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    borrower TEXT NOT NULL,
+    obligation TEXT NOT NULL,
+    asset TEXT NOT NULL,
+    amount TEXT NOT NULL,
+    borrow_fee TEXT NOT NULL,
+    time TEXT NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX borrow_events_v2_epoch_idx ON borrow_events_v2 (epoch);
+CREATE INDEX borrow_events_v2_obligation_idx ON borrow_events_v2 (obligation);
+
+CREATE TABLE borrow_flash_loan_events (
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    epoch INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    borrower TEXT NOT NULL,
+    asset TEXT NOT NULL,
+    amount TEXT NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX borrow_flash_loan_events_epoch_idx ON borrow_flash_loan_events (epoch);
+
+CREATE TABLE collateral_deposit_events (
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    epoch INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    provider TEXT NOT NULL,
+    obligation TEXT NOT NULL,
+    deposit_asset TEXT NOT NULL,
+    deposit_amount TEXT NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX collateral_deposit_events_epoch_idx ON collateral_deposit_events (epoch);
+CREATE INDEX collateral_deposit_events_obligation_idx ON collateral_deposit_events (obligation);
+
+CREATE TABLE collateral_withdraw_events (
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    epoch INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    taker TEXT NOT NULL,
+    obligation TEXT NOT NULL,
+    withdraw_asset TEXT NOT NULL,
+    withdraw_amount TEXT NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX collateral_withdraw_events_epoch_idx ON collateral_withdraw_events (epoch);
+CREATE INDEX collateral_withdraw_events_obligation_idx ON collateral_withdraw_events (obligation);
+
+CREATE TABLE liquidate_events (
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    epoch INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    liquidator TEXT NOT NULL,
+    obligation TEXT NOT NULL,
+    debt_type TEXT NOT NULL,
+    collateral_type TEXT NOT NULL,
+    repay_on_behalf TEXT NOT NULL,
+    repay_revenue TEXT NOT NULL,
+    liq_amount TEXT NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX liquidate_events_epoch_idx ON liquidate_events (epoch);
+CREATE INDEX liquidate_events_obligation_idx ON liquidate_events (obligation);
+
+CREATE TABLE mint_events (
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    epoch INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    minter TEXT NOT NULL,
+    deposit_asset TEXT NOT NULL,
+    deposit_amount TEXT NOT NULL,
+    mint_asset TEXT NOT NULL,
+    mint_amount TEXT NOT NULL,
+    time TEXT NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX mint_events_epoch_idx ON mint_events (epoch);
+
+CREATE TABLE obligation_created_events (
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    epoch INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    sender TEXT NOT NULL,
+    obligation TEXT NOT NULL,
+    obligation_key TEXT NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX obligation_created_events_epoch_idx ON obligation_created_events (epoch);
+CREATE INDEX obligation_created_events_obligation_idx ON obligation_created_events (obligation);
+
+CREATE TABLE obligation_locked_events (
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    epoch INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    obligation TEXT NOT NULL,
+    witness TEXT NOT NULL,
+    borrow_locked INTEGER NOT NULL,
+    repay_locked INTEGER NOT NULL,
+    deposit_collateral_locked INTEGER NOT NULL,
+    withdraw_collateral_locked INTEGER NOT NULL,
+    liquidate_locked INTEGER NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX obligation_locked_events_epoch_idx ON obligation_locked_events (epoch);
+CREATE INDEX obligation_locked_events_obligation_idx ON obligation_locked_events (obligation);
+
+CREATE TABLE obligation_unlocked_events (
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    epoch INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    obligation TEXT NOT NULL,
+    witness TEXT NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX obligation_unlocked_events_epoch_idx ON obligation_unlocked_events (epoch);
+CREATE INDEX obligation_unlocked_events_obligation_idx ON obligation_unlocked_events (obligation);
+
+CREATE TABLE redeem_events (
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    epoch INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    redeemer TEXT NOT NULL,
+    withdraw_asset TEXT NOT NULL,
+    withdraw_amount TEXT NOT NULL,
+    burn_asset TEXT NOT NULL,
+    burn_amount TEXT NOT NULL,
+    time TEXT NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX redeem_events_epoch_idx ON redeem_events (epoch);
+
+CREATE TABLE repay_events (
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    epoch INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    repayer TEXT NOT NULL,
+    obligation TEXT NOT NULL,
+    asset TEXT NOT NULL,
+    amount TEXT NOT NULL,
+    time TEXT NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX repay_events_epoch_idx ON repay_events (epoch);
+CREATE INDEX repay_events_obligation_idx ON repay_events (obligation);
+
+CREATE TABLE repay_flash_loan_events (
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    epoch INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    borrower TEXT NOT NULL,
+    asset TEXT NOT NULL,
+    amount TEXT NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX repay_flash_loan_events_epoch_idx ON repay_flash_loan_events (epoch);
@@ -0,0 +1,14 @@
+-- Same schema as migrations/postgres/0003_obligation_positions.sql.
+CREATE TABLE obligations (
+    obligation TEXT PRIMARY KEY,
+    checkpoint INTEGER NOT NULL
+);
+
+CREATE TABLE obligation_positions (
+    obligation TEXT NOT NULL,
+    side TEXT NOT NULL,
+    asset TEXT NOT NULL,
+    amount TEXT NOT NULL,
+    PRIMARY KEY (obligation, side, asset)
+);
+CREATE INDEX obligation_positions_asset_idx ON obligation_positions (asset, side);
@@ -0,0 +1,18 @@
+-- Same schema as migrations/postgres/0004_objects.sql.
+CREATE TABLE objects (
+    object_id TEXT PRIMARY KEY,
+    version INTEGER NOT NULL,
+    type TEXT NOT NULL,
+    owner TEXT NOT NULL,
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    deleted INTEGER NOT NULL,
+    data BLOB NOT NULL,
+    decoded TEXT,
+    error TEXT,
+    obligation TEXT,
+    asset TEXT,
+    amount TEXT
+);
+CREATE INDEX objects_type_idx ON objects (type);
+CREATE INDEX objects_obligation_idx ON objects (obligation) WHERE obligation IS NOT NULL;
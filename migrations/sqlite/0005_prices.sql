@@ -0,0 +1,10 @@
+-- Same schema as migrations/postgres/0005_prices.sql.
+CREATE TABLE prices (
+    asset TEXT NOT NULL,
+    checkpoint INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    value TEXT NOT NULL,
+    last_updated TEXT NOT NULL,
+    PRIMARY KEY (asset, checkpoint)
+);
+ALTER TABLE events ADD COLUMN prices TEXT;
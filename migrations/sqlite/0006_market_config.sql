@@ -0,0 +1,85 @@
+-- Same schema as migrations/postgres/0006_market_config.sql.
+
+CREATE TABLE interest_model_added_events (
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    epoch INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    asset TEXT NOT NULL,
+    base_borrow_rate_per_sec TEXT NOT NULL,
+    interest_rate_scale TEXT NOT NULL,
+    borrow_rate_on_mid_kink TEXT NOT NULL,
+    mid_kink TEXT NOT NULL,
+    borrow_rate_on_high_kink TEXT NOT NULL,
+    high_kink TEXT NOT NULL,
+    max_borrow_rate TEXT NOT NULL,
+    revenue_factor TEXT NOT NULL,
+    borrow_weight TEXT NOT NULL,
+    min_borrow_amount TEXT NOT NULL,
+    current_epoch TEXT NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX interest_model_added_events_asset_idx ON interest_model_added_events (asset, checkpoint);
+
+CREATE TABLE interest_model_change_created_events (
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    epoch INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    asset TEXT NOT NULL,
+    base_borrow_rate_per_sec TEXT NOT NULL,
+    interest_rate_scale TEXT NOT NULL,
+    borrow_rate_on_mid_kink TEXT NOT NULL,
+    mid_kink TEXT NOT NULL,
+    borrow_rate_on_high_kink TEXT NOT NULL,
+    high_kink TEXT NOT NULL,
+    max_borrow_rate TEXT NOT NULL,
+    revenue_factor TEXT NOT NULL,
+    borrow_weight TEXT NOT NULL,
+    min_borrow_amount TEXT NOT NULL,
+    current_epoch TEXT NOT NULL,
+    delay_epoches TEXT NOT NULL,
+    effective_epoches TEXT NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX interest_model_change_created_events_asset_idx ON interest_model_change_created_events (asset, checkpoint);
+
+CREATE TABLE risk_model_added_events (
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    epoch INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    asset TEXT NOT NULL,
+    collateral_factor TEXT NOT NULL,
+    liquidation_factor TEXT NOT NULL,
+    liquidation_penalty TEXT NOT NULL,
+    liquidation_discount TEXT NOT NULL,
+    liquidation_revenue_factor TEXT NOT NULL,
+    max_collateral_amount TEXT NOT NULL,
+    current_epoch TEXT NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX risk_model_added_events_asset_idx ON risk_model_added_events (asset, checkpoint);
+
+CREATE TABLE risk_model_change_created_events (
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    epoch INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    asset TEXT NOT NULL,
+    collateral_factor TEXT NOT NULL,
+    liquidation_factor TEXT NOT NULL,
+    liquidation_penalty TEXT NOT NULL,
+    liquidation_discount TEXT NOT NULL,
+    liquidation_revenue_factor TEXT NOT NULL,
+    max_collateral_amount TEXT NOT NULL,
+    current_epoch TEXT NOT NULL,
+    delay_epoches TEXT NOT NULL,
+    effective_epoches TEXT NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX risk_model_change_created_events_asset_idx ON risk_model_change_created_events (asset, checkpoint);
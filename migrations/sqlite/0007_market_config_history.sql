@@ -0,0 +1,52 @@
+-- Same schema as migrations/postgres/0007_market_config_history.sql.
+
+CREATE TABLE borrow_limit_changed_events (
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    epoch INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    asset TEXT NOT NULL,
+    borrow_limit TEXT NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX borrow_limit_changed_events_asset_idx ON borrow_limit_changed_events (asset, checkpoint);
+
+CREATE TABLE supply_limit_changed_events (
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    epoch INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    asset TEXT NOT NULL,
+    supply_limit TEXT NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX supply_limit_changed_events_asset_idx ON supply_limit_changed_events (asset, checkpoint);
+
+CREATE TABLE interest_accrued_events (
+    checkpoint INTEGER NOT NULL,
+    digest TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    epoch INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    asset TEXT NOT NULL,
+    borrow_index TEXT NOT NULL,
+    interest_rate TEXT NOT NULL,
+    interest_rate_scale TEXT NOT NULL,
+    last_updated TEXT NOT NULL,
+    PRIMARY KEY (checkpoint, digest, event_index)
+);
+CREATE INDEX interest_accrued_events_asset_idx ON interest_accrued_events (asset, checkpoint);
+
+CREATE TABLE market_config (
+    asset TEXT NOT NULL,
+    checkpoint INTEGER NOT NULL,
+    config TEXT NOT NULL,
+    collateral_factor TEXT,
+    liquidation_factor TEXT,
+    borrow_weight TEXT,
+    borrow_limit TEXT,
+    supply_limit TEXT,
+    PRIMARY KEY (asset, checkpoint)
+);
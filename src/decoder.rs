@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::{Arc, OnceLock};
+use bcs::from_bytes;
+use log::warn;
+use move_core_types::account_address::AccountAddress;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sui_types::parse_sui_struct_tag;
+use crate::events::ScallopEvent;
+
+/// Fully qualified Move struct of event (package address, module, struct name), type parameters are ignored.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EventType {
+    pub address: AccountAddress,
+    pub module: String,
+    pub name: String,
+}
+
+impl EventType {
+    pub fn new(address: &str, module: &str, name: &str) -> Self {
+        EventType { address: AccountAddress::from_hex_literal(address).unwrap(), module: module.to_string(), name: name.to_string() }
+    }
+
+    pub fn parse(type_: &str) -> Option<Self> {
+        let tag = parse_sui_struct_tag(type_).ok()?;
+        return Some(EventType { address: tag.address, module: tag.module.to_string(), name: tag.name.to_string() });
+    }
+}
+
+/// Decoded event content, Scallop events are typed, events of other registered protocols are kept as json.
+#[derive(Serialize,Deserialize,Debug)]
+#[serde(untagged)]
+pub enum EventPayload {
+    Scallop(ScallopEvent),
+    Json(serde_json::Value),
+}
+
+/// Successfully decoded event with its name and obligation id if event belongs to obligation.
+#[derive(Serialize,Deserialize,Debug)]
+pub struct DecodedEvent {
+    pub event: EventPayload,
+    pub name: String,
+    pub obligation: Option<String>,
+}
+
+impl DecodedEvent {
+    pub fn scallop(&self) -> Option<&ScallopEvent> {
+        return match &self.event {
+            EventPayload::Scallop(event) => Some(event),
+            EventPayload::Json(_) => None,
+        };
+    }
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+    /// There is no decoder for the event type.
+    UnknownType(String),
+    /// Event bytes do not match the expected struct layout.
+    Layout { type_: String, error: bcs::Error },
+    /// Struct was decoded, but some bytes were left - layout was most likely extended by package upgrade.
+    TrailingBytes { type_: String },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnknownType(type_) => write!(f, "unknown event type {}", type_),
+            DecodeError::Layout { type_, error } => write!(f, "layout mismatch for {}: {}", type_, error),
+            DecodeError::TrailingBytes { type_ } => write!(f, "trailing bytes after decoding {}", type_),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Decodes BCS bytes of event into `T`, all bytes have to be consumed.
+pub fn decode_bcs<T: DeserializeOwned>(bytes: &[u8], type_: &str) -> Result<T, DecodeError> {
+    return from_bytes::<T>(bytes).map_err(|error| match error {
+        bcs::Error::RemainingInput => DecodeError::TrailingBytes { type_: type_.to_string() },
+        error => DecodeError::Layout { type_: type_.to_string(), error },
+    });
+}
+
+/// Decodes contents of one event type, registered in `DecoderRegistry`.
+pub trait EventDecoder: Send + Sync {
+    fn decode(&self, bytes: &[u8], event_type: &EventType, type_: &str) -> Result<DecodedEvent, DecodeError>;
+}
+
+/// Decoder for protocols without typed payload, event is decoded into `T` and stored as json.
+/// `obligation` extracts obligation (position) id if the event belongs to one.
+pub struct JsonDecoder<T> {
+    obligation: fn(&T) -> Option<String>,
+    _event: PhantomData<fn() -> T>,
+}
+
+impl<T> JsonDecoder<T> {
+    pub fn new(obligation: fn(&T) -> Option<String>) -> Self {
+        JsonDecoder { obligation, _event: PhantomData }
+    }
+}
+
+impl<T: DeserializeOwned + Serialize> EventDecoder for JsonDecoder<T> {
+    fn decode(&self, bytes: &[u8], event_type: &EventType, type_: &str) -> Result<DecodedEvent, DecodeError> {
+        let event = decode_bcs::<T>(bytes, type_)?;
+        let obligation = (self.obligation)(&event);
+        let value = serde_json::to_value(&event).unwrap_or(serde_json::Value::Null);
+        return Ok(DecodedEvent { event: EventPayload::Json(value), name: event_type.name.clone(), obligation });
+    }
+}
+
+/// Maps Move struct types of events to their decoders, types without decoder are unknown.
+#[derive(Default, Clone)]
+pub struct DecoderRegistry {
+    decoders: HashMap<EventType, Arc<dyn EventDecoder>>,
+}
+
+impl DecoderRegistry {
+    pub fn new() -> Self {
+        DecoderRegistry::default()
+    }
+
+    /// Registry with Scallop decoders, see `events::register`.
+    pub fn scallop() -> Self {
+        let mut registry = DecoderRegistry::new();
+        crate::events::register(&mut registry);
+        return registry;
+    }
+
+    /// Shared registry with Scallop decoders used by `events::parse`.
+    pub fn default_registry() -> &'static DecoderRegistry {
+        static REGISTRY: OnceLock<DecoderRegistry> = OnceLock::new();
+        return REGISTRY.get_or_init(DecoderRegistry::scallop);
+    }
+
+    pub fn register(&mut self, event_type: EventType, decoder: Arc<dyn EventDecoder>) {
+        if self.decoders.insert(event_type.clone(), decoder).is_some() {
+            warn!("decoder for {:?} replaced", event_type);
+        }
+    }
+
+    pub fn contains(&self, event_type: &EventType) -> bool {
+        return self.decoders.contains_key(event_type);
+    }
+
+    pub fn decode(&self, bytes: &[u8], type_: &str) -> Result<DecodedEvent, DecodeError> {
+        let event_type = EventType::parse(type_).ok_or(DecodeError::UnknownType(type_.to_string()))?;
+        return match self.decoders.get(&event_type) {
+            Some(decoder) => decoder.decode(bytes, &event_type, type_),
+            None => {
+                warn!("pattern for parsing event not found ... {}", type_);
+                Err(DecodeError::UnknownType(type_.to_string()))
+            }
+        };
+    }
+}
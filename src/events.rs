@@ -1,7 +1,9 @@
-use bcs::from_bytes;
-use log::warn;
+use std::sync::Arc;
 use serde::{Deserialize, Serialize};
-use sui_types::base_types::{ObjectID, SuiAddress};
+use sui_types::base_types::SuiAddress;
+use crate::decoder::{decode_bcs, DecoderRegistry, EventDecoder};
+use crate::filter::PackageFilter;
+pub use crate::decoder::{DecodeError, DecodedEvent, EventPayload, EventType};
 use sui_types::full_checkpoint_content::CheckpointData;
 use sui_types::id::ID;
 
@@ -20,75 +22,193 @@ pub enum ScallopEvent {
     RedeemEvent(RedeemEvent),
     RepayEvent(RepayEvent),
     RepayFlashLoanEvent(RepayFlashLoanEvent),
-    Unknown
+    InterestModelAdded(InterestModelAdded),
+    InterestModelChangeCreated(InterestModelChangeCreated),
+    RiskModelAdded(RiskModelAdded),
+    RiskModelChangeCreated(RiskModelChangeCreated),
+    BorrowLimitChangedEvent(BorrowLimitChangedEvent),
+    SupplyLimitChangedEvent(SupplyLimitChangedEvent),
+    InterestAccruedEvent(InterestAccruedEvent),
 }
 
-
-// in this moment not sure if the events have constant size ...
-pub fn parse(bytes: &[u8], type_: &str) -> Option<(ScallopEvent, String, Option<String>)> {
-    let result = type_.split("::").last().unwrap();
-    match result {
-        "BorrowEvent" => {
-            let event = from_bytes::<BorrowEvent>(&bytes).unwrap();
-            return Some((ScallopEvent::BorrowEvent(event.clone()),  result.to_string(), Some(event.obligation.bytes.to_string())));
-        }
-        "BorrowFlashLoanEvent" => {
-            let event = from_bytes::<BorrowFlashLoanEvent>(&bytes).unwrap();
-            return Some((ScallopEvent::BorrowFlashLoanEvent(event), result.to_string(), None));
-        }
-        "BorrowEventV2" => {
-            let event = from_bytes::<BorrowEventV2>(&bytes).unwrap();
-            return Some((ScallopEvent::BorrowEventV2(event.clone()), result.to_string(), Some(event.obligation.bytes.to_string()) ));
-        }
-        "CollateralDepositEvent" => {
-            let event = from_bytes::<CollateralDepositEvent>(&bytes).unwrap();
-            return Some((ScallopEvent::CollateralDepositEvent(event.clone()), result.to_string(), Some(event.obligation.bytes.to_string())));
-        }
-        "CollateralWithdrawEvent" => {
-            let event = from_bytes::<CollateralWithdrawEvent>(&bytes).unwrap();
-            return Some((ScallopEvent::CollateralWithdrawEvent(event.clone()), result.to_string(), Some(event.obligation.bytes.to_string())));
-        }
-        "LiquidateEvent" => {
-            let event = from_bytes::<LiquidateEvent>(&bytes).unwrap();
-            return Some((ScallopEvent::LiquidateEvent(event.clone()), result.to_string(), Some(event.obligation.bytes.to_string())));
-        }
-        "MintEvent" => {
-            let event = from_bytes::<MintEvent>(&bytes).unwrap();
-            return Some((ScallopEvent::MintEvent(event), result.to_string(), None ));
-        }
-        // ObligationCreated too ...
-        "ObligationCreatedEvent" => {
-            let event = from_bytes::<ObligationCreatedEvent>(&bytes).unwrap();
-            return Some((ScallopEvent::ObligationCreatedEvent(event.clone()), result.to_string(), Some(event.obligation.bytes.to_string())));
-        }
-        "ObligationLocked" => {
-            let event = from_bytes::<ObligationLocked>(&bytes).unwrap();
-            return Some((ScallopEvent::ObligationLocked(event.clone()), result.to_string(), Some(event.obligation.bytes.to_string()) ));
-        }
-        "ObligationUnlocked" => {
-            let event = from_bytes::<ObligationUnlocked>(&bytes).unwrap();
-            return Some((ScallopEvent::ObligationUnlocked(event.clone()), result.to_string(), Some(event.obligation.bytes.to_string())));
+impl ScallopEvent {
+    /// Assets (`TypeName`) moved by the event.
+    pub fn assets(&self) -> Vec<&str> {
+        match self {
+            ScallopEvent::BorrowEvent(event) => vec![&event.asset.name],
+            ScallopEvent::BorrowEventV2(event) => vec![&event.asset.name],
+            ScallopEvent::BorrowFlashLoanEvent(event) => vec![&event.asset.name],
+            ScallopEvent::CollateralDepositEvent(event) => vec![&event.deposit_asset.name],
+            ScallopEvent::CollateralWithdrawEvent(event) => vec![&event.withdraw_asset.name],
+            ScallopEvent::LiquidateEvent(event) => vec![&event.debt_type.name, &event.collateral_type.name],
+            ScallopEvent::MintEvent(event) => vec![&event.deposit_asset.name, &event.mint_asset.name],
+            ScallopEvent::RedeemEvent(event) => vec![&event.withdraw_asset.name, &event.burn_asset.name],
+            ScallopEvent::RepayEvent(event) => vec![&event.asset.name],
+            ScallopEvent::RepayFlashLoanEvent(event) => vec![&event.asset.name],
+            ScallopEvent::ObligationCreatedEvent(_) | ScallopEvent::ObligationLocked(_) | ScallopEvent::ObligationUnlocked(_) => vec![],
+            ScallopEvent::InterestModelAdded(_) | ScallopEvent::InterestModelChangeCreated(_)
+            | ScallopEvent::RiskModelAdded(_) | ScallopEvent::RiskModelChangeCreated(_)
+            | ScallopEvent::BorrowLimitChangedEvent(_) | ScallopEvent::SupplyLimitChangedEvent(_)
+            | ScallopEvent::InterestAccruedEvent(_) => vec![],
         }
-        "RedeemEvent" => {
-            let event = from_bytes::<RedeemEvent>(&bytes).unwrap();
-            return Some((ScallopEvent::RedeemEvent(event),result.to_string(), None));
-        }
-        "RepayEvent" => {
-            let event = from_bytes::<RepayEvent>(&bytes).unwrap();
-            return Some((ScallopEvent::RepayEvent(event.clone()),result.to_string(), Some(event.obligation.bytes.to_string())));
-        }
-        "RepayFlashLoanEvent" => {
-            let event = from_bytes::<RepayFlashLoanEvent>(&bytes).unwrap();
-            return Some((ScallopEvent::RepayFlashLoanEvent(event), result.to_string(), None));
-        }
-        _ => {
-            warn!("pattern for parsing event not found ...");
-            let mut unknown = "unkown::".to_string();
-            unknown.push_str(result);
-            return Some((ScallopEvent::Unknown, unknown, None))
+    }
+
+    /// Asset (`TypeName`) of market configuration event, interest accrual does not change configuration.
+    pub fn market_asset(&self) -> Option<&str> {
+        match self {
+            ScallopEvent::InterestModelAdded(event) => Some(&event.interest_model.type_.name),
+            ScallopEvent::InterestModelChangeCreated(event) => Some(&event.interest_model.type_.name),
+            ScallopEvent::RiskModelAdded(event) => Some(&event.risk_model.type_.name),
+            ScallopEvent::RiskModelChangeCreated(event) => Some(&event.risk_model.type_.name),
+            ScallopEvent::BorrowLimitChangedEvent(event) => Some(&event.asset.name),
+            ScallopEvent::SupplyLimitChangedEvent(event) => Some(&event.asset.name),
+            _ => None,
         }
     }
-    return None;
+}
+
+/// Original Scallop protocol package and its upgrades, all of them are indexed by `FilterConfig::scallop`.
+pub const SCALLOP_PACKAGE: &str = "0xefe8b36d5b2e43728cc323298626b83177803521d195cfb11e15b910e892fddf";
+/// Upgrade which introduced `borrow::BorrowEventV2`.
+pub const SCALLOP_PACKAGE_V2: &str = "0xc38f849e81cfe46d4e4320f508ea7dda42934a329d5a6571bb4c3cb6ea63f5da";
+/// Upgrade without decoders of its own. Events emitted by its functions with structs of the original package
+/// are decoded, structs first defined in it are not known and are stored as dead letters with raw data.
+pub const SCALLOP_PACKAGE_V3: &str = "0x41c0788f4ab64cf36dc882174f467634c033bf68c3c1b5ef9819507825eb510b";
+
+/// Layouts known for Scallop events.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScallopEventKind {
+    BorrowEvent,
+    BorrowEventV2,
+    BorrowFlashLoanEvent,
+    CollateralDepositEvent,
+    CollateralWithdrawEvent,
+    LiquidateEvent,
+    MintEvent,
+    ObligationCreatedEvent,
+    ObligationLocked,
+    ObligationUnlocked,
+    RedeemEvent,
+    RepayEvent,
+    RepayFlashLoanEvent,
+    InterestModelAdded,
+    InterestModelChangeCreated,
+    RiskModelAdded,
+    RiskModelChangeCreated,
+    BorrowLimitChangedEvent,
+    SupplyLimitChangedEvent,
+    InterestAccruedEvent,
+}
+
+/// Event types of Scallop with their layouts, struct defined in original package keeps its address
+/// after upgrades, so upgrade packages are listed only with structs they introduced.
+pub fn scallop_event_types() -> Vec<(EventType, ScallopEventKind)> {
+    let events = [
+        (SCALLOP_PACKAGE, "borrow", "BorrowEvent", ScallopEventKind::BorrowEvent),
+        (SCALLOP_PACKAGE, "flash_loan", "BorrowFlashLoanEvent", ScallopEventKind::BorrowFlashLoanEvent),
+        (SCALLOP_PACKAGE, "flash_loan", "RepayFlashLoanEvent", ScallopEventKind::RepayFlashLoanEvent),
+        (SCALLOP_PACKAGE, "deposit_collateral", "CollateralDepositEvent", ScallopEventKind::CollateralDepositEvent),
+        (SCALLOP_PACKAGE, "withdraw_collateral", "CollateralWithdrawEvent", ScallopEventKind::CollateralWithdrawEvent),
+        (SCALLOP_PACKAGE, "liquidate", "LiquidateEvent", ScallopEventKind::LiquidateEvent),
+        (SCALLOP_PACKAGE, "mint", "MintEvent", ScallopEventKind::MintEvent),
+        (SCALLOP_PACKAGE, "redeem", "RedeemEvent", ScallopEventKind::RedeemEvent),
+        (SCALLOP_PACKAGE, "repay", "RepayEvent", ScallopEventKind::RepayEvent),
+        (SCALLOP_PACKAGE, "open_obligation", "ObligationCreatedEvent", ScallopEventKind::ObligationCreatedEvent),
+        (SCALLOP_PACKAGE, "lock_obligation", "ObligationLocked", ScallopEventKind::ObligationLocked),
+        (SCALLOP_PACKAGE, "lock_obligation", "ObligationUnlocked", ScallopEventKind::ObligationUnlocked),
+        (SCALLOP_PACKAGE, "interest_model", "InterestModelAdded", ScallopEventKind::InterestModelAdded),
+        (SCALLOP_PACKAGE, "interest_model", "InterestModelChangeCreated", ScallopEventKind::InterestModelChangeCreated),
+        (SCALLOP_PACKAGE, "risk_model", "RiskModelAdded", ScallopEventKind::RiskModelAdded),
+        (SCALLOP_PACKAGE, "risk_model", "RiskModelChangeCreated", ScallopEventKind::RiskModelChangeCreated),
+        (SCALLOP_PACKAGE, "app", "BorrowLimitChangedEvent", ScallopEventKind::BorrowLimitChangedEvent),
+        (SCALLOP_PACKAGE, "app", "SupplyLimitChangedEvent", ScallopEventKind::SupplyLimitChangedEvent),
+        (SCALLOP_PACKAGE, "market", "InterestAccruedEvent", ScallopEventKind::InterestAccruedEvent),
+        (SCALLOP_PACKAGE_V2, "borrow", "BorrowEventV2", ScallopEventKind::BorrowEventV2),
+    ];
+    return events.into_iter()
+        .map(|(address, module, name, kind)| (EventType::new(address, module, name), kind))
+        .collect();
+}
+
+/// Registers Scallop decoders.
+pub fn register(registry: &mut DecoderRegistry) {
+    for (event_type, kind) in scallop_event_types() {
+        registry.register(event_type, Arc::new(ScallopDecoder { kind }));
+    }
+}
+
+/// Decodes typed `ScallopEvent` of given layout.
+pub struct ScallopDecoder {
+    pub kind: ScallopEventKind,
+}
+
+impl EventDecoder for ScallopDecoder {
+    fn decode(&self, bytes: &[u8], event_type: &EventType, type_: &str) -> Result<DecodedEvent, DecodeError> {
+        let (event, obligation) = match self.kind {
+            ScallopEventKind::BorrowEvent => {
+                let event = decode_bcs::<BorrowEvent>(bytes, type_)?;
+                let obligation = event.obligation.bytes.to_string();
+                (ScallopEvent::BorrowEvent(event), Some(obligation))
+            }
+            ScallopEventKind::BorrowFlashLoanEvent => (ScallopEvent::BorrowFlashLoanEvent(decode_bcs(bytes, type_)?), None),
+            ScallopEventKind::BorrowEventV2 => {
+                let event = decode_bcs::<BorrowEventV2>(bytes, type_)?;
+                let obligation = event.obligation.bytes.to_string();
+                (ScallopEvent::BorrowEventV2(event), Some(obligation))
+            }
+            ScallopEventKind::CollateralDepositEvent => {
+                let event = decode_bcs::<CollateralDepositEvent>(bytes, type_)?;
+                let obligation = event.obligation.bytes.to_string();
+                (ScallopEvent::CollateralDepositEvent(event), Some(obligation))
+            }
+            ScallopEventKind::CollateralWithdrawEvent => {
+                let event = decode_bcs::<CollateralWithdrawEvent>(bytes, type_)?;
+                let obligation = event.obligation.bytes.to_string();
+                (ScallopEvent::CollateralWithdrawEvent(event), Some(obligation))
+            }
+            ScallopEventKind::LiquidateEvent => {
+                let event = decode_bcs::<LiquidateEvent>(bytes, type_)?;
+                let obligation = event.obligation.bytes.to_string();
+                (ScallopEvent::LiquidateEvent(event), Some(obligation))
+            }
+            ScallopEventKind::MintEvent => (ScallopEvent::MintEvent(decode_bcs(bytes, type_)?), None),
+            ScallopEventKind::ObligationCreatedEvent => {
+                let event = decode_bcs::<ObligationCreatedEvent>(bytes, type_)?;
+                let obligation = event.obligation.bytes.to_string();
+                (ScallopEvent::ObligationCreatedEvent(event), Some(obligation))
+            }
+            ScallopEventKind::ObligationLocked => {
+                let event = decode_bcs::<ObligationLocked>(bytes, type_)?;
+                let obligation = event.obligation.bytes.to_string();
+                (ScallopEvent::ObligationLocked(event), Some(obligation))
+            }
+            ScallopEventKind::ObligationUnlocked => {
+                let event = decode_bcs::<ObligationUnlocked>(bytes, type_)?;
+                let obligation = event.obligation.bytes.to_string();
+                (ScallopEvent::ObligationUnlocked(event), Some(obligation))
+            }
+            ScallopEventKind::RedeemEvent => (ScallopEvent::RedeemEvent(decode_bcs(bytes, type_)?), None),
+            ScallopEventKind::RepayEvent => {
+                let event = decode_bcs::<RepayEvent>(bytes, type_)?;
+                let obligation = event.obligation.bytes.to_string();
+                (ScallopEvent::RepayEvent(event), Some(obligation))
+            }
+            ScallopEventKind::RepayFlashLoanEvent => (ScallopEvent::RepayFlashLoanEvent(decode_bcs(bytes, type_)?), None),
+            ScallopEventKind::InterestModelAdded => (ScallopEvent::InterestModelAdded(decode_bcs(bytes, type_)?), None),
+            ScallopEventKind::InterestModelChangeCreated => (ScallopEvent::InterestModelChangeCreated(decode_bcs(bytes, type_)?), None),
+            ScallopEventKind::RiskModelAdded => (ScallopEvent::RiskModelAdded(decode_bcs(bytes, type_)?), None),
+            ScallopEventKind::RiskModelChangeCreated => (ScallopEvent::RiskModelChangeCreated(decode_bcs(bytes, type_)?), None),
+            ScallopEventKind::BorrowLimitChangedEvent => (ScallopEvent::BorrowLimitChangedEvent(decode_bcs(bytes, type_)?), None),
+            ScallopEventKind::SupplyLimitChangedEvent => (ScallopEvent::SupplyLimitChangedEvent(decode_bcs(bytes, type_)?), None),
+            ScallopEventKind::InterestAccruedEvent => (ScallopEvent::InterestAccruedEvent(decode_bcs(bytes, type_)?), None),
+        };
+        return Ok(DecodedEvent { event: EventPayload::Scallop(event), name: event_type.name.clone(), obligation });
+    }
+}
+
+/// Decodes event with the default (Scallop) registry.
+pub fn parse(bytes: &[u8], type_: &str) -> Result<DecodedEvent, DecodeError> {
+    return DecoderRegistry::default_registry().decode(bytes, type_);
 }
 
 #[derive(Serialize,Deserialize,Debug, Clone)]
@@ -99,20 +219,23 @@ pub struct IndexerData {
     pub data: Vec<u8>,
     pub index: u64,
     pub type_: String,
+    /// Sender of transaction which emitted the event, missing in data stored before it was added.
+    #[serde(default)]
+    pub sender: String,
 }
 
 impl IndexerData {
-    pub fn parse_event(&self) -> Option<(ScallopEvent, String, Option<String>)> {
+    pub fn parse_event(&self) -> Result<DecodedEvent, DecodeError> {
         return parse(&self.data, &self.type_);
     }
 }
 
-pub fn process_txn(data: &CheckpointData, filter: &Vec<String>) -> Vec<(String, IndexerData)>{
+pub fn process_txn(data: &CheckpointData, filter: &PackageFilter) -> Vec<(String, IndexerData)>{
     let mut results = vec![];
     for txn in data.transactions.iter() {
         for events in txn.events.iter() {
             for (idx, event) in events.data.iter().enumerate() {
-                if filter.contains(&event.type_.address.to_string()) || filter.contains(&event.package_id.to_string()) {
+                if filter.matches(event) {
                     let digest = txn.transaction.digest().to_string();
                     let result = IndexerData{
                         digest: digest.clone(),
@@ -121,6 +244,7 @@ pub fn process_txn(data: &CheckpointData, filter: &Vec<String>) -> Vec<(String,
                         data: event.contents.clone(),
                         index: idx as u64,
                         type_: event.type_.to_string(),
+                        sender: event.sender.to_string(),
                     };
                     results.push((digest, result));
                 }
@@ -244,7 +368,102 @@ pub struct RepayFlashLoanEvent {
     pub amount: u64
 }
 
-#[derive(Serialize,Deserialize,Debug,Clone)]
+#[derive(Serialize,Deserialize,Debug,Clone,PartialEq)]
 pub struct TypeName {
     pub name: String
-}
\ No newline at end of file
+}
+/// `math::fixed_point32::FixedPoint32`, number with 32 fractional bits.
+#[derive(Serialize,Deserialize,Debug,Clone,Copy,PartialEq)]
+pub struct FixedPoint32 {
+    pub value: u64
+}
+
+impl FixedPoint32 {
+    pub fn to_f64(&self) -> f64 {
+        return self.value as f64 / (1u64 << 32) as f64;
+    }
+}
+
+/// Interest rate curve and borrow parameters of one asset, rates are per second.
+#[derive(Serialize,Deserialize,Debug,Clone,PartialEq)]
+pub struct InterestModel {
+    #[serde(rename = "type")]
+    pub type_: TypeName,
+    pub base_borrow_rate_per_sec: FixedPoint32,
+    pub interest_rate_scale: u64,
+    pub borrow_rate_on_mid_kink: FixedPoint32,
+    pub mid_kink: FixedPoint32,
+    pub borrow_rate_on_high_kink: FixedPoint32,
+    pub high_kink: FixedPoint32,
+    pub max_borrow_rate: FixedPoint32,
+    pub revenue_factor: FixedPoint32,
+    pub borrow_weight: FixedPoint32,
+    pub min_borrow_amount: u64
+}
+
+/// Collateral parameters of one asset, `max_collateral_amount` is the collateral supply limit.
+#[derive(Serialize,Deserialize,Debug,Clone,PartialEq)]
+pub struct RiskModel {
+    #[serde(rename = "type")]
+    pub type_: TypeName,
+    pub collateral_factor: FixedPoint32,
+    pub liquidation_factor: FixedPoint32,
+    pub liquidation_penalty: FixedPoint32,
+    pub liquidation_discount: FixedPoint32,
+    pub liquidation_revenue_factor: FixedPoint32,
+    pub max_collateral_amount: u64
+}
+
+/// Interest model applied to market, emitted when asset is listed and when a change becomes effective.
+#[derive(Serialize,Deserialize,Debug,Clone)]
+pub struct InterestModelAdded {
+    pub interest_model: InterestModel,
+    pub current_epoch: u64
+}
+
+/// Interest model change proposed by admin, it can be applied after `delay_epoches`.
+#[derive(Serialize,Deserialize,Debug,Clone)]
+pub struct InterestModelChangeCreated {
+    pub interest_model: InterestModel,
+    pub current_epoch: u64,
+    pub delay_epoches: u64,
+    pub effective_epoches: u64
+}
+
+#[derive(Serialize,Deserialize,Debug,Clone)]
+pub struct RiskModelAdded {
+    pub risk_model: RiskModel,
+    pub current_epoch: u64
+}
+
+#[derive(Serialize,Deserialize,Debug,Clone)]
+pub struct RiskModelChangeCreated {
+    pub risk_model: RiskModel,
+    pub current_epoch: u64,
+    pub delay_epoches: u64,
+    pub effective_epoches: u64
+}
+
+/// Maximum total borrow of asset set by admin, applied right away.
+#[derive(Serialize,Deserialize,Debug,Clone)]
+pub struct BorrowLimitChangedEvent {
+    pub asset: TypeName,
+    pub limit: u64
+}
+
+/// Maximum total supply of asset set by admin, applied right away.
+#[derive(Serialize,Deserialize,Debug,Clone)]
+pub struct SupplyLimitChangedEvent {
+    pub asset: TypeName,
+    pub limit: u64
+}
+
+/// Interest accrued to borrows of asset, `borrow_index` grows by `interest_rate` since `last_updated` (seconds).
+#[derive(Serialize,Deserialize,Debug,Clone)]
+pub struct InterestAccruedEvent {
+    pub asset: TypeName,
+    pub borrow_index: u64,
+    pub interest_rate: FixedPoint32,
+    pub interest_rate_scale: u64,
+    pub last_updated: u64
+}
@@ -0,0 +1,94 @@
+use std::ops::Range;
+use std::time::Duration;
+use anyhow::{anyhow, Result};
+use bytes::Bytes;
+use futures::{stream, Stream, StreamExt};
+use log::{debug, warn};
+use sui_storage::blob::Blob;
+use sui_types::full_checkpoint_content::CheckpointData;
+use sui_types::messages_checkpoint::CheckpointSequenceNumber;
+use tokio::time::sleep;
+
+pub const MAINNET_ARCHIVE_URL: &str = "https://checkpoints.mainnet.sui.io";
+pub const TESTNET_ARCHIVE_URL: &str = "https://checkpoints.testnet.sui.io";
+
+/// Fetches checkpoints from checkpoint archive ({base_url}/{sequence_number}.chk).
+/// Number of requests in flight is bounded by `concurrency`, requests failing with 429, 5xx
+/// or transport error are retried with exponential backoff and checkpoints are returned in sequence order.
+#[derive(Clone)]
+pub struct ArchiveFetcher {
+    pub base_url: String,
+    pub concurrency: usize,
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    client: reqwest::Client,
+}
+
+impl ArchiveFetcher {
+    pub fn new(base_url: &str) -> Self {
+        ArchiveFetcher {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            concurrency: 50,
+            max_retries: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Returns raw checkpoint blob, `None` if checkpoint is not (yet) in the archive.
+    pub async fn fetch_bytes(&self, sequence_number: CheckpointSequenceNumber) -> Result<Option<Bytes>> {
+        let url = format!("{}/{}.chk", self.base_url, sequence_number);
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 0;
+        loop {
+            let error = match self.client.get(&url).send().await {
+                Ok(response) => match response.status().as_u16() {
+                    200 => match response.bytes().await {
+                        Ok(bytes) => return Ok(Some(bytes)),
+                        Err(err) => anyhow!("reading body failed: {}", err),
+                    },
+                    // archive answers 403 or 404 for objects which are not uploaded yet
+                    403 | 404 => return Ok(None),
+                    429 | 500..=599 => anyhow!("archive returned {}", response.status()),
+                    status_code => return Err(anyhow!("archive returned {} for checkpoint {}", status_code, sequence_number)),
+                },
+                Err(err) => anyhow!("request failed: {}", err),
+            };
+            attempt += 1;
+            if attempt > self.max_retries {
+                return Err(error.context(format!("fetching checkpoint {} failed after {} retries", sequence_number, self.max_retries)));
+            }
+            debug!("checkpoint {} attempt {} failed: {}, retrying in {:?}", sequence_number, attempt, error, backoff);
+            sleep(backoff).await;
+            backoff = (backoff * 2).min(self.max_backoff);
+        }
+    }
+
+    pub async fn fetch(&self, sequence_number: CheckpointSequenceNumber) -> Result<Option<CheckpointData>> {
+        return match self.fetch_bytes(sequence_number).await? {
+            Some(bytes) => Ok(Some(Blob::from_bytes::<CheckpointData>(&bytes)?)),
+            None => Ok(None),
+        };
+    }
+
+    /// Fetches checkpoints of `range` with at most `concurrency` requests in flight,
+    /// items are yielded in sequence order.
+    pub fn stream(&self, range: Range<CheckpointSequenceNumber>) -> impl Stream<Item = Result<(CheckpointSequenceNumber, Option<CheckpointData>)>> + Send + 'static {
+        let fetcher = self.clone();
+        let concurrency = self.concurrency.max(1);
+        stream::iter(range)
+            .map(move |sequence_number| {
+                let fetcher = fetcher.clone();
+                async move {
+                    let checkpoint = fetcher.fetch(sequence_number).await;
+                    if let Err(err) = &checkpoint {
+                        warn!("{:?}", err);
+                    }
+                    checkpoint.map(|checkpoint| (sequence_number, checkpoint))
+                }
+            })
+            .buffered(concurrency)
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::str::FromStr;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sui_types::base_types::ObjectID;
+use sui_types::event::Event;
+use crate::events::{SCALLOP_PACKAGE, SCALLOP_PACKAGE_V2, SCALLOP_PACKAGE_V3};
+
+/// Indexed package, `include` and `exclude` entries are `module` or `module::Struct` of event type.
+/// Empty `include` means all events of the package, `exclude` wins over `include`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PackageRule {
+    pub id: ObjectID,
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+/// Filter configuration, eg. toml file:
+/// ```toml
+/// [[packages]]
+/// id = "0xefe8b36d5b2e43728cc323298626b83177803521d195cfb11e15b910e892fddf"
+/// exclude = ["flash_loan"]
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct FilterConfig {
+    #[serde(default)]
+    pub packages: Vec<PackageRule>,
+}
+
+impl FilterConfig {
+    /// Scallop protocol packages, all events.
+    pub fn scallop() -> Self {
+        let packages = [SCALLOP_PACKAGE, SCALLOP_PACKAGE_V2, SCALLOP_PACKAGE_V3].iter()
+            .map(|id| PackageRule { id: ObjectID::from_str(id).unwrap(), include: vec![], exclude: vec![] })
+            .collect();
+        FilterConfig { packages }
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self> {
+        return Ok(toml::from_str(content)?);
+    }
+
+    pub fn from_file(path: &str) -> Result<Self> {
+        return Self::from_toml(&fs::read_to_string(path)?);
+    }
+
+    /// Configuration of --filter file and --package / --include / --exclude flags. Without file and packages
+    /// Scallop packages are indexed and --include / --exclude rules are added to them.
+    pub fn from_flags(file: Option<&str>, packages: &[String], include: &[String], exclude: &[String]) -> Result<Self> {
+        let mut config = match file {
+            Some(path) => Self::from_file(path)?,
+            None if packages.is_empty() => Self::scallop(),
+            None => Self::default(),
+        };
+        for package in packages.iter() {
+            config.add_package(package)?;
+        }
+        for selector in include.iter() {
+            config.add_include(selector)?;
+        }
+        for selector in exclude.iter() {
+            config.add_exclude(selector)?;
+        }
+        return Ok(config);
+    }
+
+    fn package(&mut self, id: ObjectID) -> &mut PackageRule {
+        if let Some(position) = self.packages.iter().position(|rule| rule.id == id) {
+            return &mut self.packages[position];
+        }
+        self.packages.push(PackageRule { id, include: vec![], exclude: vec![] });
+        return self.packages.last_mut().unwrap();
+    }
+
+    pub fn add_package(&mut self, id: &str) -> Result<()> {
+        self.package(ObjectID::from_str(id)?);
+        Ok(())
+    }
+
+    /// Adds `package::module` or `package::module::Struct` to includes of the package.
+    pub fn add_include(&mut self, selector: &str) -> Result<()> {
+        let (id, selector) = Self::split_selector(selector)?;
+        self.package(id).include.push(selector);
+        Ok(())
+    }
+
+    /// Adds `package::module` or `package::module::Struct` to excludes of the package.
+    pub fn add_exclude(&mut self, selector: &str) -> Result<()> {
+        let (id, selector) = Self::split_selector(selector)?;
+        self.package(id).exclude.push(selector);
+        Ok(())
+    }
+
+    fn split_selector(selector: &str) -> Result<(ObjectID, String)> {
+        let (id, rest) = selector.split_once("::").ok_or(anyhow!("expected package::module[::Struct], got {}", selector))?;
+        return Ok((ObjectID::from_str(id)?, rest.to_string()));
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Selector {
+    module: String,
+    name: Option<String>,
+}
+
+impl Selector {
+    fn parse(selector: &str) -> Self {
+        match selector.split_once("::") {
+            Some((module, name)) => Selector { module: module.to_string(), name: Some(name.to_string()) },
+            None => Selector { module: selector.to_string(), name: None },
+        }
+    }
+
+    fn matches(&self, module: &str, name: &str) -> bool {
+        return self.module == module && self.name.as_ref().map_or(true, |selected| selected == name);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Rules {
+    include: Vec<Selector>,
+    exclude: Vec<Selector>,
+}
+
+/// Compiled `FilterConfig`, decides which events are indexed.
+#[derive(Debug, Clone, Default)]
+pub struct PackageFilter {
+    packages: HashSet<ObjectID>,
+    rules: HashMap<ObjectID, Rules>,
+}
+
+impl PackageFilter {
+    pub fn new(config: &FilterConfig) -> Self {
+        let mut filter = PackageFilter::default();
+        for rule in config.packages.iter() {
+            filter.packages.insert(rule.id);
+            let rules = filter.rules.entry(rule.id).or_default();
+            rules.include.extend(rule.include.iter().map(|selector| Selector::parse(selector)));
+            rules.exclude.extend(rule.exclude.iter().map(|selector| Selector::parse(selector)));
+        }
+        return filter;
+    }
+
+    /// All events of given packages.
+    pub fn from_packages(packages: impl IntoIterator<Item = ObjectID>) -> Self {
+        PackageFilter { packages: packages.into_iter().collect(), rules: HashMap::new() }
+    }
+
+    pub fn packages(&self) -> &HashSet<ObjectID> {
+        return &self.packages;
+    }
+
+    /// Event matches when its type is defined in one of the packages or it was emitted by one of them.
+    pub fn matches(&self, event: &Event) -> bool {
+        let type_package = ObjectID::from(event.type_.address);
+        let package = if self.packages.contains(&type_package) {
+            type_package
+        } else if self.packages.contains(&event.package_id) {
+            event.package_id
+        } else {
+            return false;
+        };
+        return self.allowed(&package, event.type_.module.as_str(), event.type_.name.as_str());
+    }
+
+    /// Applies include / exclude rules of the package to event type `module::name`.
+    pub fn allowed(&self, package: &ObjectID, module: &str, name: &str) -> bool {
+        if !self.packages.contains(package) {
+            return false;
+        }
+        let rules = match self.rules.get(package) {
+            Some(rules) => rules,
+            None => return true,
+        };
+        if rules.exclude.iter().any(|selector| selector.matches(module, name)) {
+            return false;
+        }
+        return rules.include.is_empty() || rules.include.iter().any(|selector| selector.matches(module, name));
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::fs;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use crate::market::MarketHistory;
+use crate::obligation::ObligationState;
+
+/// Risk parameters of one asset, `asset` is its `TypeName` (without 0x, as in events).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AssetRisk {
+    pub asset: String,
+    /// Amounts are in smallest units, value of amount is `amount / 10^decimals * price`.
+    pub decimals: u8,
+    /// Part of collateral value which can be borrowed against.
+    pub collateral_factor: f64,
+    /// Part of collateral value counted for liquidation, obligation is liquidatable when weighted debt is above it.
+    pub liquidation_factor: f64,
+    /// Debt value is multiplied by borrow weight.
+    #[serde(default = "default_borrow_weight")]
+    pub borrow_weight: f64,
+}
+
+fn default_borrow_weight() -> f64 {
+    1.0
+}
+
+/// Risk parameters and prices, eg. toml file:
+/// ```toml
+/// [[assets]]
+/// asset = "0000000000000000000000000000000000000000000000000000000000000002::sui::SUI"
+/// decimals = 9
+/// collateral_factor = 0.6
+/// liquidation_factor = 0.8
+///
+/// [prices]
+/// "0000000000000000000000000000000000000000000000000000000000000002::sui::SUI" = 1.25
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RiskConfig {
+    #[serde(default)]
+    pub assets: Vec<AssetRisk>,
+    /// USD price of one whole coin by asset.
+    #[serde(default)]
+    pub prices: HashMap<String, f64>,
+}
+
+impl RiskConfig {
+    pub fn from_toml(content: &str) -> Result<Self> {
+        return Ok(toml::from_str(content)?);
+    }
+
+    pub fn from_file(path: &str) -> Result<Self> {
+        return Self::from_toml(&fs::read_to_string(path)?);
+    }
+}
+
+/// Risk of one obligation, values are in USD.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Health {
+    pub obligation: String,
+    pub collateral_value: f64,
+    /// Debt value multiplied by borrow weights.
+    pub debt_value: f64,
+    /// Collateral value multiplied by collateral factors.
+    pub borrow_capacity: f64,
+    /// Collateral value multiplied by liquidation factors.
+    pub liquidation_value: f64,
+    /// `liquidation_value / debt_value`, obligation is liquidatable below 1, none without debt.
+    pub health_factor: Option<f64>,
+    /// Assets of obligation without price or risk parameters, they are not counted.
+    pub missing: Vec<String>,
+}
+
+impl Health {
+    /// Value which can be still borrowed.
+    pub fn available_borrow(&self) -> f64 {
+        return (self.borrow_capacity - self.debt_value).max(0.0);
+    }
+
+    pub fn liquidatable(&self) -> bool {
+        return self.health_factor.is_some_and(|health_factor| health_factor < 1.0);
+    }
+}
+
+/// Computes health of obligations from their positions, same inputs always give the same result.
+/// Debts are taken as they are, for positions reconstructed from events they are principal without interest.
+#[derive(Debug, Clone, Default)]
+pub struct HealthCalculator {
+    assets: HashMap<String, AssetRisk>,
+    prices: HashMap<String, f64>,
+}
+
+impl HealthCalculator {
+    pub fn new(config: &RiskConfig) -> Self {
+        HealthCalculator {
+            assets: config.assets.iter().map(|risk| (risk.asset.clone(), risk.clone())).collect(),
+            prices: config.prices.clone(),
+        }
+    }
+
+    pub fn set_price(&mut self, asset: &str, price: f64) {
+        self.prices.insert(asset.to_string(), price);
+    }
+
+    pub fn set_risk(&mut self, risk: AssetRisk) {
+        self.assets.insert(risk.asset.clone(), risk);
+    }
+
+    /// Replaces risk parameters of configured assets by market configuration in effect at checkpoint
+    /// (see `Pipeline::market`), decimals are kept. Assets without risk model in history keep their parameters.
+    pub fn apply_market(&mut self, market: &MarketHistory, checkpoint: u64) {
+        for risk in self.assets.values_mut() {
+            if let Some(updated) = market.at(&risk.asset, checkpoint).and_then(|config| config.asset_risk(risk.decimals)) {
+                *risk = updated;
+            }
+        }
+    }
+
+    /// USD value of amount with risk parameters of its asset, none when price or parameters are unknown.
+    fn value(&self, asset: &str, amount: u64) -> Option<(f64, &AssetRisk)> {
+        let risk = self.assets.get(asset)?;
+        let price = self.prices.get(asset)?;
+        return Some((amount as f64 / 10f64.powi(risk.decimals as i32) * price, risk));
+    }
+
+    pub fn health(&self, state: &ObligationState) -> Health {
+        let mut health = Health {
+            obligation: state.id.clone(),
+            collateral_value: 0.0,
+            debt_value: 0.0,
+            borrow_capacity: 0.0,
+            liquidation_value: 0.0,
+            health_factor: None,
+            missing: vec![],
+        };
+        // maps are ordered, so values are summed in the same order every time
+        for (asset, amount) in state.collaterals.iter() {
+            match self.value(asset, *amount) {
+                Some((value, risk)) => {
+                    health.collateral_value += value;
+                    health.borrow_capacity += value * risk.collateral_factor;
+                    health.liquidation_value += value * risk.liquidation_factor;
+                }
+                None => health.missing.push(asset.clone()),
+            }
+        }
+        for (asset, amount) in state.debts.iter() {
+            match self.value(asset, *amount) {
+                Some((value, risk)) => health.debt_value += value * risk.borrow_weight,
+                None => health.missing.push(asset.clone()),
+            }
+        }
+        if health.debt_value > 0.0 {
+            health.health_factor = Some(health.liquidation_value / health.debt_value);
+        }
+        return health;
+    }
+
+    /// Obligations with health factor below `threshold`, the riskiest first.
+    pub fn below<'a>(&self, states: impl IntoIterator<Item = &'a ObligationState>, threshold: f64) -> Vec<Health> {
+        let mut risky: Vec<Health> = states.into_iter()
+            .map(|state| self.health(state))
+            .filter(|health| health.health_factor.is_some_and(|health_factor| health_factor < threshold))
+            .collect();
+        risky.sort_by(|a, b| {
+            let (a_factor, b_factor) = (a.health_factor.unwrap_or(f64::INFINITY), b.health_factor.unwrap_or(f64::INFINITY));
+            a_factor.total_cmp(&b_factor).then_with(|| a.obligation.cmp(&b.obligation))
+        });
+        return risky;
+    }
+}
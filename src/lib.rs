@@ -1,2 +1,12 @@
+pub mod decoder;
 pub mod events;
-pub mod reader;
\ No newline at end of file
+pub mod fetcher;
+pub mod filter;
+pub mod health;
+pub mod market;
+pub mod obligation;
+pub mod objects;
+pub mod pipeline;
+pub mod prices;
+pub mod reader;
+pub mod sink;
\ No newline at end of file
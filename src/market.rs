@@ -0,0 +1,126 @@
+use std::collections::{BTreeMap, HashMap};
+use serde::{Deserialize, Serialize};
+use crate::events::{InterestModel, RiskModel, ScallopEvent};
+use crate::health::AssetRisk;
+use crate::sink::CheckpointBatch;
+
+/// Interest and risk model and limits of one asset in effect since `checkpoint`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MarketConfig {
+    pub asset: String,
+    pub checkpoint: u64,
+    pub interest_model: Option<InterestModel>,
+    pub risk_model: Option<RiskModel>,
+    /// Maximum total borrow of asset, none until it is set by `BorrowLimitChangedEvent`.
+    #[serde(default)]
+    pub borrow_limit: Option<u64>,
+    /// Maximum total supply of asset, none until it is set by `SupplyLimitChangedEvent`.
+    #[serde(default)]
+    pub supply_limit: Option<u64>,
+}
+
+impl MarketConfig {
+    /// Risk parameters for `HealthCalculator`, decimals of asset are not part of market configuration.
+    pub fn asset_risk(&self, decimals: u8) -> Option<AssetRisk> {
+        let risk_model = self.risk_model.as_ref()?;
+        Some(AssetRisk {
+            asset: self.asset.clone(),
+            decimals,
+            collateral_factor: risk_model.collateral_factor.to_f64(),
+            liquidation_factor: risk_model.liquidation_factor.to_f64(),
+            borrow_weight: self.interest_model.as_ref().map_or(1.0, |model| model.borrow_weight.to_f64()),
+        })
+    }
+}
+
+/// History of market configuration of each asset from `InterestModelAdded`, `RiskModelAdded` and limit change
+/// events, checkpoints have to be applied in sequence order. Proposed changes (`*ChangeCreated`) are not in effect
+/// until they are applied, which emits `*Added` event again. Pipeline loads the latest stored configuration
+/// of asset (`insert`) before it applies events of asset which is not in the history yet.
+#[derive(Debug, Default)]
+pub struct MarketHistory {
+    configs: HashMap<String, BTreeMap<u64, MarketConfig>>,
+}
+
+impl MarketHistory {
+    pub fn new() -> Self {
+        MarketHistory::default()
+    }
+
+    /// Applies event of checkpoint, returns false when event does not change market configuration.
+    pub fn apply(&mut self, checkpoint: u64, event: &ScallopEvent) -> bool {
+        let asset = match (event, event.market_asset()) {
+            (ScallopEvent::InterestModelAdded(_) | ScallopEvent::RiskModelAdded(_)
+            | ScallopEvent::BorrowLimitChangedEvent(_) | ScallopEvent::SupplyLimitChangedEvent(_), Some(asset)) => asset,
+            _ => return false,
+        };
+        let history = self.configs.entry(asset.to_string()).or_default();
+        // the new configuration starts as copy of the previous one, only changed model or limit is replaced
+        let mut config = match history.values().next_back() {
+            Some(config) => config.clone(),
+            None => MarketConfig {
+                asset: asset.to_string(),
+                checkpoint,
+                interest_model: None,
+                risk_model: None,
+                borrow_limit: None,
+                supply_limit: None,
+            },
+        };
+        config.checkpoint = checkpoint;
+        match event {
+            ScallopEvent::InterestModelAdded(event) => config.interest_model = Some(event.interest_model.clone()),
+            ScallopEvent::RiskModelAdded(event) => config.risk_model = Some(event.risk_model.clone()),
+            ScallopEvent::BorrowLimitChangedEvent(event) => config.borrow_limit = Some(event.limit),
+            ScallopEvent::SupplyLimitChangedEvent(event) => config.supply_limit = Some(event.limit),
+            _ => {}
+        }
+        history.insert(checkpoint, config);
+        return true;
+    }
+
+    /// Applies market events of checkpoint in their order, returns configurations which were changed.
+    pub fn apply_batch(&mut self, batch: &CheckpointBatch) -> Vec<MarketConfig> {
+        let mut changed: Vec<String> = vec![];
+        for event in batch.events.iter() {
+            let scallop = match event.decoded.as_ref().ok().and_then(|decoded| decoded.scallop()) {
+                Some(scallop) => scallop,
+                None => continue,
+            };
+            if !self.apply(batch.checkpoint, scallop) {
+                continue;
+            }
+            if let Some(asset) = scallop.market_asset() {
+                if !changed.iter().any(|changed| changed == asset) {
+                    changed.push(asset.to_string());
+                }
+            }
+        }
+        return changed.iter().filter_map(|asset| self.at(asset, batch.checkpoint).cloned()).collect();
+    }
+
+    /// Configuration of asset in effect at checkpoint (including changes made in the checkpoint).
+    pub fn at(&self, asset: &str, checkpoint: u64) -> Option<&MarketConfig> {
+        return self.configs.get(asset)?.range(..=checkpoint).next_back().map(|(_, config)| config);
+    }
+
+    /// Every configuration of asset, the oldest first.
+    pub fn history(&self, asset: &str) -> Vec<&MarketConfig> {
+        return self.configs.get(asset).map(|history| history.values().collect()).unwrap_or_default();
+    }
+
+    pub fn contains(&self, asset: &str) -> bool {
+        return self.configs.contains_key(asset);
+    }
+
+    /// Adds stored configuration, eg. the latest one of asset when indexing continues after restart.
+    pub fn insert(&mut self, config: MarketConfig) {
+        self.configs.entry(config.asset.clone()).or_default().insert(config.checkpoint, config);
+    }
+
+    pub fn assets(&self) -> Vec<&String> {
+        let mut assets: Vec<&String> = self.configs.keys().collect();
+        assets.sort();
+        return assets;
+    }
+}
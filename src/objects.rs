@@ -0,0 +1,278 @@
+use std::collections::{HashMap, HashSet};
+use anyhow::{anyhow, Result};
+use move_core_types::account_address::AccountAddress;
+use move_core_types::language_storage::{StructTag, TypeTag};
+use serde::{Deserialize, Serialize};
+use sui_types::base_types::ObjectID;
+use sui_types::effects::TransactionEffectsAPI;
+use sui_types::full_checkpoint_content::CheckpointData;
+use sui_types::id::UID;
+use sui_types::object::{Object, Owner};
+use crate::decoder::{decode_bcs, DecodeError, EventType};
+use crate::events::{TypeName, SCALLOP_PACKAGE};
+
+const DYNAMIC_FIELD_MODULE: &str = "dynamic_field";
+const DYNAMIC_FIELD_STRUCT: &str = "Field";
+
+/// Indexed Move object types, type parameters are ignored. Dynamic fields (`0x2::dynamic_field::Field<K, V>`)
+/// are matched by type of their value, eg. `obligation_debts::Debt` selects debt entries of obligations.
+#[derive(Debug, Clone, Default)]
+pub struct ObjectFilter {
+    types: HashSet<EventType>,
+}
+
+impl ObjectFilter {
+    /// Types given as `package::module::Struct`.
+    pub fn new(types: &[String]) -> Result<Self> {
+        let types = types.iter()
+            .map(|type_| EventType::parse(type_).ok_or(anyhow!("expected package::module::Struct, got {}", type_)))
+            .collect::<Result<HashSet<EventType>>>()?;
+        Ok(ObjectFilter { types })
+    }
+
+    /// Scallop obligations with their debt and collateral entries and markets.
+    pub fn scallop() -> Self {
+        let types = [
+            ("obligation", "Obligation"),
+            ("obligation_debts", "Debt"),
+            ("obligation_collaterals", "Collateral"),
+            ("market", "Market"),
+        ];
+        ObjectFilter { types: types.iter().map(|(module, name)| EventType::new(SCALLOP_PACKAGE, module, name)).collect() }
+    }
+
+    pub fn matches(&self, tag: &StructTag) -> bool {
+        return self.types.contains(&selected_type(tag));
+    }
+}
+
+/// Type of object used for selection and decoding, value type for dynamic fields.
+pub(crate) fn selected_type(tag: &StructTag) -> EventType {
+    if tag.module.as_str() == DYNAMIC_FIELD_MODULE && tag.name.as_str() == DYNAMIC_FIELD_STRUCT {
+        if let Some(TypeTag::Struct(value)) = tag.type_params.last() {
+            return struct_type(value);
+        }
+    }
+    return struct_type(tag);
+}
+
+fn struct_type(tag: &StructTag) -> EventType {
+    EventType { address: tag.address, module: tag.module.to_string(), name: tag.name.to_string() }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Table {
+    pub id: UID,
+    pub size: u64,
+}
+
+/// `x::wit_table::WitTable`, entries are dynamic fields of `table`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WitTable {
+    pub id: UID,
+    pub table: Table,
+    pub keys: Vec<TypeName>,
+    pub with_keys: bool,
+}
+
+/// `x::balance_bag::BalanceBag`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BalanceBag {
+    pub id: UID,
+    pub bag: Table,
+}
+
+/// `protocol::obligation::Obligation`, amounts are in dynamic fields of `debts` and `collaterals` tables.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ObligationObject {
+    pub id: UID,
+    pub balances: BalanceBag,
+    pub debts: WitTable,
+    pub collaterals: WitTable,
+    pub rewards_point: u64,
+    pub lock_key: Option<TypeName>,
+    pub borrow_locked: bool,
+    pub repay_locked: bool,
+    pub deposit_collateral_locked: bool,
+    pub withdraw_collateral_locked: bool,
+    pub liquidate_locked: bool,
+}
+
+/// Debt including interest accrued until the last change of obligation (`borrow_index` of that time).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Debt {
+    pub amount: u64,
+    pub borrow_index: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Collateral {
+    pub amount: u64,
+}
+
+/// `0x2::dynamic_field::Field<Name, Value>`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Field<Name, Value> {
+    pub id: UID,
+    pub name: Name,
+    pub value: Value,
+}
+
+/// Decoded Scallop object, objects of other types are stored only as bcs.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "kind")]
+pub enum ScallopObject {
+    Obligation(ObligationObject),
+    Debt { asset: String, amount: u64, borrow_index: u64 },
+    Collateral { asset: String, amount: u64 },
+}
+
+impl ScallopObject {
+    pub fn decode(tag: &StructTag, bytes: &[u8]) -> Result<Option<Self>, DecodeError> {
+        let selected = selected_type(tag);
+        if selected.address != AccountAddress::from_hex_literal(SCALLOP_PACKAGE).unwrap() {
+            return Ok(None);
+        }
+        let type_ = tag.to_string();
+        let object = match (selected.module.as_str(), selected.name.as_str()) {
+            ("obligation", "Obligation") => ScallopObject::Obligation(decode_bcs(bytes, &type_)?),
+            ("obligation_debts", "Debt") => {
+                let field: Field<TypeName, Debt> = decode_bcs(bytes, &type_)?;
+                ScallopObject::Debt { asset: field.name.name, amount: field.value.amount, borrow_index: field.value.borrow_index }
+            }
+            ("obligation_collaterals", "Collateral") => {
+                let field: Field<TypeName, Collateral> = decode_bcs(bytes, &type_)?;
+                ScallopObject::Collateral { asset: field.name.name, amount: field.value.amount }
+            }
+            _ => return Ok(None),
+        };
+        return Ok(Some(object));
+    }
+}
+
+/// Version of matched object changed (or deleted) by transaction.
+#[derive(Debug)]
+pub struct ObjectSnapshot {
+    pub id: String,
+    pub version: u64,
+    pub type_: String,
+    /// Address or parent object, `shared` or `immutable`.
+    pub owner: String,
+    pub checkpoint: u64,
+    /// Digest of transaction which produced the version.
+    pub digest: String,
+    /// Bcs contents, for deleted object the last contents before deletion.
+    pub data: Vec<u8>,
+    pub deleted: bool,
+    pub decoded: Result<Option<ScallopObject>, DecodeError>,
+    /// Obligation of the object, for debt and collateral entries resolved through tables of obligation.
+    pub obligation: Option<String>,
+}
+
+impl ObjectSnapshot {
+    fn new(object: &Object, tag: &StructTag, checkpoint: u64, digest: &str) -> Option<Self> {
+        let contents = object.data.try_as_move()?.contents();
+        Some(ObjectSnapshot {
+            id: object.id().to_string(),
+            version: object.version().value(),
+            type_: tag.to_string(),
+            owner: owner(&object.owner),
+            checkpoint,
+            digest: digest.to_string(),
+            data: contents.to_vec(),
+            deleted: false,
+            decoded: ScallopObject::decode(tag, contents),
+            obligation: None,
+        })
+    }
+
+    pub fn scallop(&self) -> Option<&ScallopObject> {
+        return self.decoded.as_ref().ok().and_then(|decoded| decoded.as_ref());
+    }
+
+    /// Snapshot as json object, decoded object is in `object`, raw bcs (hex) only when decoding failed.
+    pub fn json(&self) -> serde_json::Value {
+        let mut value = serde_json::json!({
+            "id": self.id,
+            "version": self.version,
+            "type": self.type_,
+            "owner": self.owner,
+            "checkpoint": self.checkpoint,
+            "digest": self.digest,
+            "deleted": self.deleted,
+            "obligation": self.obligation,
+        });
+        match &self.decoded {
+            Ok(decoded) => {
+                value["object"] = serde_json::to_value(decoded).unwrap_or(serde_json::Value::Null);
+            }
+            Err(err) => {
+                value["error"] = serde_json::json!(err.to_string());
+                value["data"] = serde_json::json!(crate::sink::hex(&self.data));
+            }
+        }
+        return value;
+    }
+}
+
+fn owner(owner: &Owner) -> String {
+    match owner {
+        Owner::AddressOwner(address) | Owner::ObjectOwner(address) => address.to_string(),
+        Owner::Immutable => "immutable".to_string(),
+        _ => "shared".to_string(),
+    }
+}
+
+/// Objects of matched types created, changed or deleted by transactions of checkpoint, in transaction order.
+pub fn process_objects(data: &CheckpointData, filter: &ObjectFilter) -> Vec<ObjectSnapshot> {
+    let checkpoint = data.checkpoint_summary.sequence_number;
+    let mut results = vec![];
+    for txn in data.transactions.iter() {
+        let digest = txn.transaction.digest().to_string();
+        let mut snapshots = vec![];
+        for object in txn.output_objects.iter() {
+            if let Some(tag) = object.struct_tag().filter(|tag| filter.matches(tag)) {
+                snapshots.extend(ObjectSnapshot::new(object, &tag, checkpoint, &digest));
+            }
+        }
+        // removed objects are not in outputs, their last contents are in inputs
+        let removed: HashMap<ObjectID, u64> = txn.effects.deleted().into_iter()
+            .chain(txn.effects.wrapped())
+            .map(|(id, version, _)| (id, version.value()))
+            .collect();
+        for object in txn.input_objects.iter() {
+            let version = match removed.get(&object.id()) {
+                Some(version) => *version,
+                None => continue,
+            };
+            if let Some(tag) = object.struct_tag().filter(|tag| filter.matches(tag)) {
+                snapshots.extend(ObjectSnapshot::new(object, &tag, checkpoint, &digest).map(|mut snapshot| {
+                    snapshot.version = version;
+                    snapshot.deleted = true;
+                    snapshot
+                }));
+            }
+        }
+        resolve_obligations(&mut snapshots);
+        results.extend(snapshots);
+    }
+    return results;
+}
+
+/// Entries of obligation tables are changed only together with the obligation, so the obligation is in the same transaction.
+fn resolve_obligations(snapshots: &mut [ObjectSnapshot]) {
+    let mut tables: HashMap<String, String> = HashMap::new();
+    for snapshot in snapshots.iter() {
+        if let Some(ScallopObject::Obligation(obligation)) = snapshot.scallop() {
+            tables.insert(obligation.debts.table.id.id.bytes.to_string(), snapshot.id.clone());
+            tables.insert(obligation.collaterals.table.id.id.bytes.to_string(), snapshot.id.clone());
+        }
+    }
+    for snapshot in snapshots.iter_mut() {
+        snapshot.obligation = match snapshot.scallop() {
+            Some(ScallopObject::Obligation(_)) => Some(snapshot.id.clone()),
+            Some(_) => tables.get(&snapshot.owner).cloned(),
+            None => None,
+        };
+    }
+}
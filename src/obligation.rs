@@ -0,0 +1,128 @@
+use std::collections::{BTreeMap, HashMap};
+use serde::{Deserialize, Serialize};
+use crate::events::ScallopEvent;
+use crate::sink::CheckpointBatch;
+
+/// Position of one obligation reconstructed from events, amounts are keyed by asset `TypeName`.
+/// Debts are borrowed principal, interest accrued since borrow is not part of events.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ObligationState {
+    pub id: String,
+    pub collaterals: BTreeMap<String, u64>,
+    pub debts: BTreeMap<String, u64>,
+    /// Last checkpoint applied to the state, events of older checkpoints are skipped.
+    pub checkpoint: u64,
+}
+
+impl ObligationState {
+    pub fn new(id: &str) -> Self {
+        ObligationState { id: id.to_string(), ..ObligationState::default() }
+    }
+
+    fn add(amounts: &mut BTreeMap<String, u64>, asset: &str, amount: u64) {
+        let value = amounts.entry(asset.to_string()).or_insert(0);
+        *value = value.saturating_add(amount);
+    }
+
+    /// Amounts can not go below zero, eg. repay of debt with interest is larger than principal.
+    fn sub(amounts: &mut BTreeMap<String, u64>, asset: &str, amount: u64) {
+        if let Some(value) = amounts.get_mut(asset) {
+            *value = value.saturating_sub(amount);
+            if *value == 0 {
+                amounts.remove(asset);
+            }
+        }
+    }
+
+    /// Applies event of this obligation, returns false when event does not change position.
+    pub fn apply(&mut self, event: &ScallopEvent) -> bool {
+        match event {
+            ScallopEvent::CollateralDepositEvent(event) => Self::add(&mut self.collaterals, &event.deposit_asset.name, event.deposit_amount),
+            ScallopEvent::CollateralWithdrawEvent(event) => Self::sub(&mut self.collaterals, &event.withdraw_asset.name, event.withdraw_amount),
+            ScallopEvent::BorrowEvent(event) => Self::add(&mut self.debts, &event.asset.name, event.amount),
+            ScallopEvent::BorrowEventV2(event) => Self::add(&mut self.debts, &event.asset.name, event.amount),
+            ScallopEvent::RepayEvent(event) => Self::sub(&mut self.debts, &event.asset.name, event.amount),
+            ScallopEvent::LiquidateEvent(event) => {
+                Self::sub(&mut self.debts, &event.debt_type.name, event.repay_on_behalf);
+                Self::sub(&mut self.collaterals, &event.collateral_type.name, event.liq_amount);
+            }
+            _ => return false,
+        }
+        return true;
+    }
+}
+
+/// Folds obligation events into current positions, checkpoints have to be applied in sequence order.
+#[derive(Debug, Default)]
+pub struct ObligationBook {
+    obligations: HashMap<String, ObligationState>,
+}
+
+impl ObligationBook {
+    pub fn new() -> Self {
+        ObligationBook::default()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ObligationState> {
+        return self.obligations.get(id);
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        return self.obligations.contains_key(id);
+    }
+
+    /// Adds state loaded from storage.
+    pub fn insert(&mut self, state: ObligationState) {
+        self.obligations.insert(state.id.clone(), state);
+    }
+
+    /// All tracked obligations, eg. for `HealthCalculator::below`.
+    pub fn states(&self) -> impl Iterator<Item = &ObligationState> {
+        return self.obligations.values();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.obligations.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.obligations.is_empty();
+    }
+
+    /// Obligations with events in the checkpoint.
+    pub fn obligation_ids(batch: &CheckpointBatch) -> Vec<String> {
+        let mut ids: Vec<String> = batch.events.iter()
+            .filter_map(|event| event.decoded.as_ref().ok())
+            .filter_map(|decoded| decoded.obligation.clone())
+            .collect();
+        ids.sort();
+        ids.dedup();
+        return ids;
+    }
+
+    /// Applies events of checkpoint in their order, returns states which were changed.
+    /// Obligations which already applied the checkpoint (eg. replay after restart) are not changed.
+    pub fn apply_batch(&mut self, batch: &CheckpointBatch) -> Vec<ObligationState> {
+        let mut changed: Vec<String> = vec![];
+        for event in batch.events.iter() {
+            let decoded = match &event.decoded {
+                Ok(decoded) => decoded,
+                Err(_) => continue,
+            };
+            let (id, scallop) = match (&decoded.obligation, decoded.scallop()) {
+                (Some(id), Some(scallop)) => (id, scallop),
+                _ => continue,
+            };
+            let state = self.obligations.entry(id.clone()).or_insert_with(|| ObligationState::new(id));
+            if !changed.contains(id) {
+                if state.checkpoint >= batch.checkpoint {
+                    continue;
+                }
+                changed.push(id.clone());
+            }
+            state.apply(scallop);
+            state.checkpoint = batch.checkpoint;
+        }
+        return changed.iter().filter_map(|id| self.obligations.get(id).cloned()).collect();
+    }
+}
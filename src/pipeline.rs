@@ -0,0 +1,258 @@
+use std::future::Future;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use anyhow::{anyhow, Result};
+use futures::{Stream, StreamExt};
+use log::{debug, info, warn};
+use sui_types::full_checkpoint_content::CheckpointData;
+use sui_types::messages_checkpoint::CheckpointSequenceNumber;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+use crate::decoder::DecoderRegistry;
+use crate::fetcher::ArchiveFetcher;
+use crate::filter::PackageFilter;
+use crate::market::MarketHistory;
+use crate::objects::{process_objects, ObjectFilter};
+use crate::obligation::ObligationBook;
+use crate::prices::Oracle;
+use crate::reader::CheckpointSource;
+use crate::sink::{CheckpointBatch, Sink, Watermark, LIVE_WATERMARK};
+
+/// Reads checkpoints from source, selects and decodes events and stores them into sink.
+#[derive(Clone)]
+pub struct Pipeline {
+    pub filter: PackageFilter,
+    pub registry: DecoderRegistry,
+    /// Return once source has no new checkpoints (or fails) instead of waiting.
+    pub exit: bool,
+    /// Failed writes and reads of sink are retried with exponential backoff up to `max_backoff` between attempts,
+    /// after `max_write_retries` failures (0 = never) the pipeline stops with error.
+    pub max_write_retries: u32,
+    pub max_backoff: Duration,
+    /// Positions of obligations are reconstructed from events and stored with each checkpoint.
+    /// They are correct only when checkpoints since the first Scallop event are indexed in order by this pipeline.
+    pub obligations: Option<Arc<Mutex<ObligationBook>>>,
+    /// Changed objects of selected types are stored with each checkpoint.
+    pub objects: Option<ObjectFilter>,
+    /// Oracle price updates are stored with each checkpoint and prices in effect are attached to events.
+    pub oracle: Option<Oracle>,
+    /// Market configuration events are applied to history and changed configurations are stored with each checkpoint.
+    pub market: Option<Arc<Mutex<MarketHistory>>>,
+}
+
+impl Pipeline {
+    pub fn new(filter: PackageFilter, registry: DecoderRegistry) -> Self {
+        Pipeline { filter, registry, exit: false, max_write_retries: 0, max_backoff: Duration::from_secs(30), obligations: None, objects: None, oracle: None, market: None }
+    }
+
+    pub fn decode(&self, checkpoint: &CheckpointData) -> CheckpointBatch {
+        let mut batch = CheckpointBatch::new(checkpoint, &self.filter, &self.registry);
+        if let Some(filter) = &self.objects {
+            batch.objects = process_objects(checkpoint, filter);
+        }
+        if let Some(oracle) = &self.oracle {
+            batch.price_updates = oracle.process(checkpoint);
+        }
+        return batch;
+    }
+
+    /// Applies obligation events of checkpoint to tracked positions, obligations not seen since start are loaded from sink.
+    pub async fn track_obligations(&self, sink: &dyn Sink, batch: &mut CheckpointBatch) -> Result<()> {
+        let book = match &self.obligations {
+            Some(book) => book,
+            None => return Ok(()),
+        };
+        let mut book = book.lock().await;
+        let missing: Vec<String> = ObligationBook::obligation_ids(batch).into_iter().filter(|id| !book.contains(id)).collect();
+        if !missing.is_empty() {
+            let loaded = self.retry(&format!("loading obligations for checkpoint {}", batch.checkpoint), || sink.obligations(&missing)).await?;
+            for state in loaded {
+                book.insert(state);
+            }
+        }
+        batch.obligations = book.apply_batch(batch);
+        Ok(())
+    }
+
+    /// Applies price updates of checkpoint and sets prices of event assets, assets not seen since start are loaded from sink.
+    pub async fn track_prices(&self, sink: &dyn Sink, batch: &mut CheckpointBatch) -> Result<()> {
+        let oracle = match &self.oracle {
+            Some(oracle) => oracle,
+            None => return Ok(()),
+        };
+        let mut book = oracle.book.lock().await;
+        book.update(&batch.price_updates);
+        let mut assets: Vec<String> = batch.events.iter()
+            .filter_map(|event| event.decoded.as_ref().ok())
+            .filter_map(|decoded| decoded.scallop())
+            .flat_map(|scallop| scallop.assets())
+            .map(|asset| asset.to_string())
+            .collect();
+        assets.sort();
+        assets.dedup();
+        let missing = book.missing(&assets);
+        if !missing.is_empty() {
+            let checkpoint = batch.checkpoint;
+            let points = self.retry(&format!("loading prices for checkpoint {}", checkpoint), || sink.latest_prices(&missing, checkpoint)).await?;
+            book.insert_loaded(&missing, points);
+        }
+        batch.prices = book.prices(&assets);
+        Ok(())
+    }
+
+    /// Applies market configuration events of checkpoint, configurations of assets not seen since start are loaded from sink.
+    pub async fn track_market(&self, sink: &dyn Sink, batch: &mut CheckpointBatch) -> Result<()> {
+        let market = match &self.market {
+            Some(market) => market,
+            None => return Ok(()),
+        };
+        let mut market = market.lock().await;
+        let mut assets: Vec<String> = batch.events.iter()
+            .filter_map(|event| event.decoded.as_ref().ok())
+            .filter_map(|decoded| decoded.scallop())
+            .filter_map(|scallop| scallop.market_asset())
+            .map(|asset| asset.to_string())
+            .collect();
+        assets.sort();
+        assets.dedup();
+        let missing: Vec<String> = assets.into_iter().filter(|asset| !market.contains(asset)).collect();
+        if !missing.is_empty() {
+            let checkpoint = batch.checkpoint;
+            let configs = self.retry(&format!("loading market configuration for checkpoint {}", checkpoint), || sink.market_configs(&missing, checkpoint)).await?;
+            for config in configs {
+                market.insert(config);
+            }
+        }
+        batch.market_configs = market.apply_batch(batch);
+        Ok(())
+    }
+
+    /// Writes batch into sink, failed write is retried so watermark never moves past data which is not stored.
+    pub async fn write(&self, sink: &dyn Sink, batch: &[CheckpointBatch], watermark: &Watermark) -> Result<()> {
+        return self.retry(&format!("storing checkpoint {}", watermark.checkpoint), || sink.write(batch, watermark)).await;
+    }
+
+    /// Runs sink operation until it succeeds, see `max_write_retries` and `max_backoff`.
+    async fn retry<T, F, Fut>(&self, operation: &str, mut run: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut backoff = Duration::from_millis(100);
+        let mut attempt = 0;
+        loop {
+            let err = match run().await {
+                Ok(result) => return Ok(result),
+                Err(err) => err,
+            };
+            attempt += 1;
+            if self.max_write_retries > 0 && attempt > self.max_write_retries {
+                return Err(err.context(format!("{} failed after {} retries", operation, self.max_write_retries)));
+            }
+            warn!("{} failed (attempt {}), retrying in {:?}: {:?}", operation, attempt, backoff, err);
+            sleep(backoff).await;
+            backoff = (backoff * 2).min(self.max_backoff);
+        }
+    }
+
+    /// Checkpoint following the last one stored by live indexing, reading of watermark is retried as writes.
+    pub async fn start_checkpoint(&self, sink: &dyn Sink) -> Result<u64> {
+        let watermark = self.retry("reading watermark", || sink.watermark(LIVE_WATERMARK)).await?;
+        return Ok(watermark.map(|watermark| watermark + 1).unwrap_or(0));
+    }
+
+    /// Live indexing, progress is stored under `LIVE_WATERMARK`.
+    pub async fn run(&self, source: &mut dyn CheckpointSource, sink: &dyn Sink) -> Result<()> {
+        loop {
+            debug!("fetching checkpoints");
+            let checkpoints = match source.next_batch().await {
+                Ok(checkpoints) => checkpoints,
+                Err(err) => {
+                    if self.exit {
+                        return Err(err);
+                    }
+                    warn!("something bad happened {:?}", err);
+                    sleep(Duration::from_millis(1000)).await;
+                    continue;
+                }
+            };
+            if checkpoints.is_empty() {
+                debug!("No checkpoints to process ...");
+                if self.exit {
+                    return sink.close().await;
+                }
+                sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+            for checkpoint_data in checkpoints.iter() {
+                let mut batch = self.decode(checkpoint_data);
+                self.track_obligations(sink, &mut batch).await?;
+                self.track_prices(sink, &mut batch).await?;
+                self.track_market(sink, &mut batch).await?;
+                let number = batch.checkpoint;
+                let watermark = Watermark { name: LIVE_WATERMARK.to_string(), checkpoint: number };
+                // source keeps the checkpoint (eg. file in ingestion directory) until it is stored
+                self.write(sink, std::slice::from_ref(&batch), &watermark).await?;
+                // stdout can be used by sink
+                info!("checkpoint {}", number);
+                if let Err(err) = source.commit(number).await {
+                    warn!("failed to commit checkpoint {}: {:?}", number, err);
+                }
+            }
+        }
+    }
+
+    /// Indexes checkpoints `from..=to` from archive, `workers` checkpoints are decoded in parallel.
+    /// Progress is stored under its own watermark so it can run next to the live indexer.
+    pub async fn backfill(&self, fetcher: &ArchiveFetcher, from: u64, to: u64, workers: usize, sink: &dyn Sink) -> Result<()> {
+        info!("backfilling from {}", fetcher.base_url);
+        return self.backfill_with(|range| fetcher.stream(range), from, to, workers, sink).await;
+    }
+
+    /// `backfill` with checkpoints of the remaining range provided by `checkpoints` in sequence order,
+    /// eg. checkpoints kept in memory.
+    pub async fn backfill_with<S>(&self, checkpoints: impl FnOnce(Range<u64>) -> S, from: u64, to: u64, workers: usize, sink: &dyn Sink) -> Result<()>
+    where
+        S: Stream<Item = Result<(CheckpointSequenceNumber, Option<CheckpointData>)>>,
+    {
+        let watermark_name = format!("backfill_{}_{}", from, to);
+        let start = self.retry("reading watermark", || sink.watermark(&watermark_name)).await?.map(|watermark| watermark + 1).unwrap_or(from);
+        if start > to {
+            info!("backfill {}..={} already done", from, to);
+            return Ok(());
+        }
+        info!("backfilling checkpoints {}..={}", start, to);
+        let pipeline = Arc::new(self.clone());
+        let mut results = checkpoints(start..to + 1)
+            .map(|result| {
+                let pipeline = pipeline.clone();
+                async move {
+                    let (number, checkpoint) = result?;
+                    let checkpoint = checkpoint.ok_or(anyhow!("checkpoint {} is not in archive", number))?;
+                    let batch = tokio::task::spawn_blocking(move || pipeline.decode(&checkpoint)).await?;
+                    Ok::<_, anyhow::Error>(batch)
+                }
+            })
+            .buffered(workers.max(1));
+        let started = SystemTime::now();
+        // results come in sequence order, so watermark always covers all checkpoints below it
+        while let Some(result) = results.next().await {
+            let mut batch = result?;
+            self.track_obligations(sink, &mut batch).await?;
+            self.track_prices(sink, &mut batch).await?;
+            self.track_market(sink, &mut batch).await?;
+            let number = batch.checkpoint;
+            let watermark = Watermark { name: watermark_name.clone(), checkpoint: number };
+            self.write(sink, std::slice::from_ref(&batch), &watermark).await?;
+            let done = number - start + 1;
+            if done % 1000 == 0 {
+                let elapsed = started.elapsed().unwrap().as_secs().max(1);
+                info!("backfill at checkpoint {}, {} checkpoints/s", number, done / elapsed);
+            }
+        }
+        sink.close().await?;
+        info!("backfill {}..={} done", from, to);
+        Ok(())
+    }
+}
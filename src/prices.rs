@@ -0,0 +1,131 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use anyhow::Result;
+use log::warn;
+use move_core_types::account_address::AccountAddress;
+use serde::{Deserialize, Serialize};
+use sui_types::full_checkpoint_content::CheckpointData;
+use tokio::sync::Mutex;
+use crate::decoder::decode_bcs;
+use crate::events::TypeName;
+use crate::objects::{selected_type, Field};
+
+/// Decimals of `PriceFeed` value, value 1_500_000_000 is 1.5 USD per whole coin.
+pub const PRICE_DECIMALS: i32 = 9;
+
+/// `x_oracle::price_feed::PriceFeed`, stored in price table of `XOracle` keyed by asset `TypeName`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PriceFeed {
+    pub value: u64,
+    /// Seconds since epoch of the oracle update.
+    pub last_updated: u64,
+}
+
+/// Price of asset stored by oracle, the last update of the asset in checkpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PricePoint {
+    pub asset: String,
+    pub checkpoint: u64,
+    pub timestamp_ms: u64,
+    pub value: u64,
+    pub last_updated: u64,
+}
+
+impl PricePoint {
+    /// USD price of one whole coin.
+    pub fn usd(&self) -> f64 {
+        return self.value as f64 / 10f64.powi(PRICE_DECIMALS);
+    }
+}
+
+/// Latest known prices, checkpoints have to be applied in sequence order.
+#[derive(Debug, Default)]
+pub struct PriceBook {
+    latest: HashMap<String, PricePoint>,
+    /// Assets already looked up in storage, assets without any price (eg. market coins) are not loaded again.
+    loaded: HashSet<String>,
+}
+
+impl PriceBook {
+    pub fn new() -> Self {
+        PriceBook::default()
+    }
+
+    pub fn get(&self, asset: &str) -> Option<&PricePoint> {
+        return self.latest.get(asset);
+    }
+
+    pub fn update(&mut self, points: &[PricePoint]) {
+        for point in points.iter() {
+            self.latest.insert(point.asset.clone(), point.clone());
+        }
+    }
+
+    /// Assets without known price which were not looked up in storage yet.
+    pub fn missing(&self, assets: &[String]) -> Vec<String> {
+        return assets.iter()
+            .filter(|asset| !self.latest.contains_key(*asset) && !self.loaded.contains(*asset))
+            .cloned()
+            .collect();
+    }
+
+    /// Adds prices loaded from storage for `assets`.
+    pub fn insert_loaded(&mut self, assets: &[String], points: Vec<PricePoint>) {
+        self.loaded.extend(assets.iter().cloned());
+        for point in points {
+            self.latest.entry(point.asset.clone()).or_insert(point);
+        }
+    }
+
+    /// Price values of assets with known price.
+    pub fn prices(&self, assets: &[String]) -> BTreeMap<String, u64> {
+        return assets.iter()
+            .filter_map(|asset| self.latest.get(asset).map(|point| (asset.clone(), point.value)))
+            .collect();
+    }
+}
+
+/// Scallop oracle (`x_oracle` package), price updates are changes of its price table entries in transaction outputs.
+/// Entries of every `PriceFeed` table of the package are indexed, oracle keeps current prices in one of them.
+#[derive(Debug, Clone)]
+pub struct Oracle {
+    pub package: AccountAddress,
+    pub book: Arc<Mutex<PriceBook>>,
+}
+
+impl Oracle {
+    pub fn new(package: &str) -> Result<Self> {
+        Ok(Oracle { package: AccountAddress::from_hex_literal(package)?, book: Arc::new(Mutex::new(PriceBook::new())) })
+    }
+
+    /// Price updates of checkpoint, the last one for each asset.
+    pub fn process(&self, data: &CheckpointData) -> Vec<PricePoint> {
+        let checkpoint = data.checkpoint_summary.sequence_number;
+        let timestamp_ms = data.checkpoint_summary.timestamp_ms;
+        let mut points: BTreeMap<String, PricePoint> = BTreeMap::new();
+        for txn in data.transactions.iter() {
+            for object in txn.output_objects.iter() {
+                let tag = match object.struct_tag() {
+                    Some(tag) => tag,
+                    None => continue,
+                };
+                let selected = selected_type(&tag);
+                if selected.address != self.package || selected.module != "price_feed" || selected.name != "PriceFeed" {
+                    continue;
+                }
+                let contents = match object.data.try_as_move() {
+                    Some(object) => object.contents(),
+                    None => continue,
+                };
+                match decode_bcs::<Field<TypeName, PriceFeed>>(contents, &tag.to_string()) {
+                    Ok(field) => {
+                        let asset = field.name.name;
+                        points.insert(asset.clone(), PricePoint { asset, checkpoint, timestamp_ms, value: field.value.value, last_updated: field.value.last_updated });
+                    }
+                    Err(err) => warn!("failed to decode price feed {}: {}", object.id(), err),
+                }
+            }
+        }
+        return points.into_values().collect();
+    }
+}
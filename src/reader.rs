@@ -1,20 +1,39 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::collections::{BTreeMap, VecDeque};
 use std::ffi::OsString;
 use std::fs;
 use std::path::PathBuf;
-use std::time::SystemTime;
+use std::time::{Duration, Instant, SystemTime};
 use sui_storage::blob::Blob;
 use sui_types::full_checkpoint_content::CheckpointData;
 use sui_types::messages_checkpoint::CheckpointSequenceNumber;
 use log::{debug, info, warn};
 use bytes::Bytes;
+use futures::{stream, StreamExt};
+use notify::event::{AccessKind, AccessMode, ModifyKind};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use reqwest::header::ACCEPT;
 use tokio::sync::mpsc;
+use tokio::time::timeout;
+use crate::fetcher::{ArchiveFetcher, MAINNET_ARCHIVE_URL};
 
 pub(crate) const ENV_VAR_LOCAL_READ_TIMEOUT_MS: &str = "LOCAL_READ_TIMEOUT_MS";
 
+/// Source of checkpoints for the processing pipeline.
+/// Checkpoints are returned in sequence order, starting from the position of the source.
+#[async_trait]
+pub trait CheckpointSource: Send {
+    /// Returns next checkpoints, empty batch means nothing new is available yet.
+    async fn next_batch(&mut self) -> Result<Vec<CheckpointData>>;
+    /// Called once `checkpoint` is stored, source moves past it and can release it.
+    async fn commit(&mut self, checkpoint: CheckpointSequenceNumber) -> Result<()>;
+}
+
 /// Implements a checkpoint reader that monitors a local directory.
 /// Designed for setups where the indexer daemon is colocated with FN.
-/// This implementation is push-based and utilizes the inotify API.
+/// Every read lists the whole directory, use `CheckpointReader::watch` for push-based mode
+/// which utilizes the inotify API.
 pub struct CheckpointReader {
     pub path: PathBuf,
     // remote_store: Option<Box<dyn ObjectStore>>,
@@ -113,6 +132,38 @@ impl CheckpointReader {
         Ok(removed)
     }
 
+    /// Switches reader to push-based mode, new checkpoint files are reported by inotify
+    /// and directory is listed again only every `rescan_interval` to catch missed events.
+    pub fn watch(self, rescan_interval: Duration) -> Result<CheckpointWatcher> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            match event {
+                Ok(event) => {
+                    // full node writes checkpoint into temporary file and renames it,
+                    // create event would come before file content is complete
+                    let complete = matches!(event.kind,
+                        EventKind::Modify(ModifyKind::Name(_)) | EventKind::Access(AccessKind::Close(AccessMode::Write)));
+                    if complete {
+                        for path in event.paths {
+                            let _ = sender.send(path);
+                        }
+                    }
+                }
+                Err(err) => warn!("watch error: {:?}", err),
+            }
+        })?;
+        watcher.watch(&self.path, RecursiveMode::NonRecursive)?;
+        info!("watching {:?} for new checkpoints", self.path);
+        Ok(CheckpointWatcher {
+            reader: self,
+            pending: BTreeMap::new(),
+            receiver,
+            rescan_interval,
+            last_rescan: None,
+            _watcher: watcher,
+        })
+    }
+
     pub fn checkpoint_number_from_file_path(file_name: &OsString) -> Option<CheckpointSequenceNumber> {
         file_name
             .to_str()
@@ -120,36 +171,220 @@ impl CheckpointReader {
             .and_then(|s| s.parse().ok())
     }
 
+    /// Fetches checkpoints `start_checkpoint..end_checkpoint` from mainnet archive in sequence order,
+    /// see `ArchiveFetcher` for other networks and fetching as a stream.
     pub async fn fetch_from_external_interval(start_checkpoint: u64, end_checkpoint: u64) -> Vec<(Option<Bytes>, u64)>{
-        let (tx, mut rx) = mpsc::unbounded_channel();
-        for i in start_checkpoint..end_checkpoint {
-            let txc = tx.clone();
-            let i = i.clone();
-            tokio::spawn(async move {
-                // println!("receiver dropped");
-                let checkpoint_file = format!("{}.chk", i);
-                let url = format!("https://checkpoints.mainnet.sui.io/{checkpoint_file}");
-                let response = reqwest::get(url).await;
-                println!("sending");
-                txc.send((response, i));
-            });
-            }
-            let mut bytes_and_checkpoint_number = vec![];
-            for i in start_checkpoint..end_checkpoint {
-                let result =  rx.recv().await;
-                if result.is_some() {
-                    let (response, checkpoint_number) = result.unwrap();
-                    if response.is_ok() {
-                        let res = response.unwrap();
-                        let bytes = res.bytes().await.unwrap();
-                        bytes_and_checkpoint_number.push((Some(bytes), checkpoint_number));
-                    } else {
-                        warn!("{:?}", response.err());
-                        bytes_and_checkpoint_number.push((None, checkpoint_number))
+        let fetcher = ArchiveFetcher::new(MAINNET_ARCHIVE_URL);
+        let concurrency = fetcher.concurrency;
+        return stream::iter(start_checkpoint..end_checkpoint)
+            .map(|i| {
+                let fetcher = &fetcher;
+                async move {
+                    match fetcher.fetch_bytes(i).await {
+                        Ok(bytes) => (bytes, i),
+                        Err(err) => {
+                            warn!("{:?}", err);
+                            (None, i)
+                        }
                     }
-                };
+                }
+            })
+            .buffered(concurrency)
+            .collect()
+            .await;
+    }
+
+}
+
+#[async_trait]
+impl CheckpointSource for CheckpointReader {
+    async fn next_batch(&mut self) -> Result<Vec<CheckpointData>> {
+        return self.read_local_files();
+    }
+
+    async fn commit(&mut self, checkpoint: CheckpointSequenceNumber) -> Result<()> {
+        let file_path = self.path.join(format!("{}.chk", checkpoint));
+        if let Err(err) = fs::remove_file(&file_path) {
+            warn!("failed to remove {:?}: {}", file_path, err);
+        }
+        self.current_checkpoint_number = checkpoint + 1;
+        Ok(())
+    }
+}
+
+/// Reads up to `batch_size` files of the local directory in directory order instead of sequence order,
+/// eg. to drain a large ingestion directory. Checkpoints of a batch are not guaranteed to be contiguous.
+pub struct RandomBatchReader {
+    pub reader: CheckpointReader,
+    pub batch_size: usize,
+}
+
+#[async_trait]
+impl CheckpointSource for RandomBatchReader {
+    async fn next_batch(&mut self) -> Result<Vec<CheckpointData>> {
+        return self.reader.read_random_batch_of_files(self.batch_size);
+    }
+
+    async fn commit(&mut self, checkpoint: CheckpointSequenceNumber) -> Result<()> {
+        return self.reader.commit(checkpoint).await;
+    }
+}
+
+/// Push-based variant of `CheckpointReader`, created by `CheckpointReader::watch`.
+pub struct CheckpointWatcher {
+    pub reader: CheckpointReader,
+    pending: BTreeMap<CheckpointSequenceNumber, PathBuf>,
+    receiver: mpsc::UnboundedReceiver<PathBuf>,
+    rescan_interval: Duration,
+    last_rescan: Option<Instant>,
+    // dropping watcher stops the notifications
+    _watcher: RecommendedWatcher,
+}
+
+impl CheckpointWatcher {
+    fn add_path(&mut self, path: PathBuf) {
+        let sequence_number = path.file_name()
+            .and_then(|name| CheckpointReader::checkpoint_number_from_file_path(&name.to_os_string()));
+        if let Some(sequence_number) = sequence_number {
+            if sequence_number >= self.reader.current_checkpoint_number {
+                self.pending.insert(sequence_number, path);
             }
-            return bytes_and_checkpoint_number;
         }
+    }
+
+    fn rescan_due(&self) -> bool {
+        self.last_rescan.map_or(true, |last| last.elapsed() >= self.rescan_interval)
+    }
+}
 
-}
\ No newline at end of file
+#[async_trait]
+impl CheckpointSource for CheckpointWatcher {
+    async fn next_batch(&mut self) -> Result<Vec<CheckpointData>> {
+        if self.rescan_due() {
+            for (sequence_number, path) in self.reader.read_all_files() {
+                self.pending.insert(sequence_number, path);
+            }
+            debug!("rescan done, pending checkpoints: {}", self.pending.len());
+            self.last_rescan = Some(Instant::now());
+        }
+        if self.pending.range(self.reader.current_checkpoint_number..).next().is_none() {
+            // nothing to do, wait for next file but not longer than until next rescan
+            let wait = self.rescan_interval.saturating_sub(self.last_rescan.unwrap().elapsed()).min(Duration::from_secs(1));
+            if let Ok(Some(path)) = timeout(wait, self.receiver.recv()).await {
+                self.add_path(path);
+            }
+        }
+        while let Ok(path) = self.receiver.try_recv() {
+            self.add_path(path);
+        }
+        let mut checkpoints = vec![];
+        for (_, path) in self.pending.range(self.reader.current_checkpoint_number..).take(MAX_CHECKPOINTS_IN_PROGRESS) {
+            checkpoints.push(self.reader.read_checkpoint(path)?);
+        }
+        Ok(checkpoints)
+    }
+
+    async fn commit(&mut self, checkpoint: CheckpointSequenceNumber) -> Result<()> {
+        self.pending.remove(&checkpoint);
+        return self.reader.commit(checkpoint).await;
+    }
+}
+
+/// Fetches full checkpoints one by one from the experimental rest api of rpc node,
+/// eg. http://localhost:9000/rest (enable-experimental-rest-api needs to be enabled on node).
+pub struct RestApiSource {
+    pub url: String,
+    pub next_checkpoint: CheckpointSequenceNumber,
+    client: reqwest::Client,
+}
+
+impl RestApiSource {
+    pub fn new(url: String, next_checkpoint: CheckpointSequenceNumber) -> Self {
+        RestApiSource { url, next_checkpoint, client: reqwest::Client::new() }
+    }
+}
+
+#[async_trait]
+impl CheckpointSource for RestApiSource {
+    async fn next_batch(&mut self) -> Result<Vec<CheckpointData>> {
+        let url = format!("{}/checkpoints/{}/full", self.url, self.next_checkpoint);
+        let response = self.client
+            .get(url)
+            .header(ACCEPT, "application/bcs")
+            .send().await?;
+        match response.status().as_u16() {
+            200 => {
+                let bytes = response.bytes().await?;
+                let checkpoint = bcs::from_bytes::<CheckpointData>(&bytes)?;
+                Ok(vec![checkpoint])
+            }
+            // 2 cases - checkpoint is too low (pruned) or does not exist in the moment
+            404 => {
+                debug!("checkpoint {} not found", self.next_checkpoint);
+                Ok(vec![])
+            }
+            status_code => Err(anyhow!("rest api returned {} for checkpoint {}", status_code, self.next_checkpoint)),
+        }
+    }
+
+    async fn commit(&mut self, checkpoint: CheckpointSequenceNumber) -> Result<()> {
+        self.next_checkpoint = checkpoint + 1;
+        Ok(())
+    }
+}
+
+/// Fetches checkpoints from checkpoint archive (checkpoints.mainnet.sui.io, testnet or local mirror) in batches.
+pub struct RemoteArchiveSource {
+    pub fetcher: ArchiveFetcher,
+    pub next_checkpoint: CheckpointSequenceNumber,
+    pub batch_size: u64,
+}
+
+#[async_trait]
+impl CheckpointSource for RemoteArchiveSource {
+    async fn next_batch(&mut self) -> Result<Vec<CheckpointData>> {
+        let end = self.next_checkpoint + self.batch_size;
+        let mut results = self.fetcher.stream(self.next_checkpoint..end);
+        let mut checkpoints = vec![];
+        // only contiguous checkpoints are returned, rest is fetched again in next batch
+        while let Some(result) = results.next().await {
+            match result {
+                Ok((_, Some(checkpoint))) => checkpoints.push(checkpoint),
+                Ok((number, None)) => {
+                    debug!("checkpoint {} not available in archive", number);
+                    break;
+                }
+                Err(err) if checkpoints.is_empty() => return Err(err),
+                Err(_) => break,
+            }
+        }
+        Ok(checkpoints)
+    }
+
+    async fn commit(&mut self, checkpoint: CheckpointSequenceNumber) -> Result<()> {
+        self.next_checkpoint = checkpoint + 1;
+        Ok(())
+    }
+}
+
+/// Serves checkpoints which are already in memory, useful for testing the pipeline.
+pub struct InMemorySource {
+    pub checkpoints: VecDeque<CheckpointData>,
+}
+
+impl InMemorySource {
+    pub fn new(checkpoints: Vec<CheckpointData>) -> Self {
+        InMemorySource { checkpoints: checkpoints.into() }
+    }
+}
+
+#[async_trait]
+impl CheckpointSource for InMemorySource {
+    async fn next_batch(&mut self) -> Result<Vec<CheckpointData>> {
+        return Ok(self.checkpoints.drain(..).collect());
+    }
+
+    async fn commit(&mut self, _checkpoint: CheckpointSequenceNumber) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use async_trait::async_trait;
+use sui_types::full_checkpoint_content::CheckpointData;
+use crate::decoder::{DecodeError, DecodedEvent, DecoderRegistry};
+use crate::events::{process_txn, IndexerData};
+use crate::filter::PackageFilter;
+use crate::market::MarketConfig;
+use crate::objects::ObjectSnapshot;
+use crate::obligation::ObligationState;
+use crate::prices::PricePoint;
+
+pub mod file;
+pub mod redis;
+pub mod relational;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "mongodb")]
+pub mod mongo;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+/// Watermark name of the live indexer.
+pub const LIVE_WATERMARK: &str = "live";
+
+/// Event selected by filter together with result of its decoding.
+#[derive(Debug)]
+pub struct IndexedEvent {
+    pub data: IndexerData,
+    pub decoded: Result<DecodedEvent, DecodeError>,
+}
+
+impl IndexedEvent {
+    /// Unique key of event, more events can have same digest ... with index is unique.
+    pub fn key(&self) -> String {
+        return format!("{}::{}::{}", self.data.checkpoint, self.data.digest, self.data.index);
+    }
+
+    /// Prices of assets of the event in effect at its checkpoint (see `CheckpointBatch::prices`).
+    pub fn prices(&self, checkpoint: &CheckpointBatch) -> BTreeMap<String, u64> {
+        let scallop = match self.decoded.as_ref().ok().and_then(|decoded| decoded.scallop()) {
+            Some(scallop) => scallop,
+            None => return BTreeMap::new(),
+        };
+        return scallop.assets().into_iter()
+            .filter_map(|asset| checkpoint.prices.get(asset).map(|value| (asset.to_string(), *value)))
+            .collect();
+    }
+
+    /// Event as json object, decoded event is in `event`, raw bcs bytes (hex) only when decoding failed.
+    pub fn json(&self, checkpoint: &CheckpointBatch) -> serde_json::Value {
+        let data = &self.data;
+        let mut value = serde_json::json!({
+            "checkpoint": data.checkpoint,
+            "digest": data.digest,
+            "index": data.index,
+            "epoch": data.epoch,
+            "timestamp_ms": checkpoint.timestamp_ms,
+            "sender": data.sender,
+            "type": data.type_,
+        });
+        match &self.decoded {
+            Ok(decoded) => {
+                value["name"] = serde_json::json!(decoded.name);
+                value["obligation"] = serde_json::json!(decoded.obligation);
+                value["event"] = serde_json::to_value(&decoded.event).unwrap_or(serde_json::Value::Null);
+                let prices = self.prices(checkpoint);
+                if !prices.is_empty() {
+                    value["prices"] = serde_json::json!(prices);
+                }
+            }
+            Err(err) => {
+                value["error"] = serde_json::json!(err.to_string());
+                value["data"] = serde_json::json!(hex(&data.data));
+            }
+        }
+        return value;
+    }
+}
+
+/// Indexed events of one checkpoint.
+#[derive(Debug)]
+pub struct CheckpointBatch {
+    pub checkpoint: u64,
+    pub epoch: u64,
+    pub timestamp_ms: u64,
+    pub events: Vec<IndexedEvent>,
+    /// Positions of obligations changed by the checkpoint, only set when pipeline tracks obligations.
+    pub obligations: Vec<ObligationState>,
+    /// Changed objects of indexed types, only set when pipeline indexes objects.
+    pub objects: Vec<ObjectSnapshot>,
+    /// Oracle price updates of the checkpoint, only set when pipeline indexes prices.
+    pub price_updates: Vec<PricePoint>,
+    /// Price values (see `prices::PRICE_DECIMALS`) of assets of the events in effect at the end of the checkpoint.
+    pub prices: BTreeMap<String, u64>,
+    /// Market configurations changed by the checkpoint, only set when pipeline tracks market configuration.
+    pub market_configs: Vec<MarketConfig>,
+}
+
+impl CheckpointBatch {
+    pub fn new(checkpoint: &CheckpointData, filter: &PackageFilter, registry: &DecoderRegistry) -> Self {
+        let events = process_txn(checkpoint, filter)
+            .into_iter()
+            .map(|(_, data)| {
+                let decoded = registry.decode(&data.data, &data.type_);
+                IndexedEvent { data, decoded }
+            })
+            .collect();
+        CheckpointBatch {
+            checkpoint: checkpoint.checkpoint_summary.sequence_number,
+            epoch: checkpoint.checkpoint_summary.epoch,
+            timestamp_ms: checkpoint.checkpoint_summary.timestamp_ms,
+            events,
+            obligations: vec![],
+            objects: vec![],
+            price_updates: vec![],
+            prices: BTreeMap::new(),
+            market_configs: vec![],
+        }
+    }
+}
+
+/// Progress of one indexing job, every checkpoint up to `checkpoint` is stored.
+#[derive(Debug, Clone)]
+pub struct Watermark {
+    pub name: String,
+    pub checkpoint: u64,
+}
+
+/// Storage backend of the indexer.
+#[async_trait]
+pub trait Sink: Send + Sync {
+    /// Stores checkpoints (in sequence order) and moves watermark to the last of them.
+    async fn write(&self, batch: &[CheckpointBatch], watermark: &Watermark) -> Result<()>;
+    /// Last checkpoint stored under watermark `name`.
+    async fn watermark(&self, name: &str) -> Result<Option<u64>>;
+    /// Stored positions of obligations (see `CheckpointBatch::obligations`), sinks which do not store them return none.
+    async fn obligations(&self, _ids: &[String]) -> Result<Vec<ObligationState>> {
+        Ok(vec![])
+    }
+    /// Latest stored prices of assets at or before `checkpoint`, sinks which do not store prices return none.
+    async fn latest_prices(&self, _assets: &[String], _checkpoint: u64) -> Result<Vec<PricePoint>> {
+        Ok(vec![])
+    }
+    /// Latest stored market configurations of assets at or before `checkpoint`, sinks which do not store them return none.
+    async fn market_configs(&self, _assets: &[String], _checkpoint: u64) -> Result<Vec<MarketConfig>> {
+        Ok(vec![])
+    }
+    /// Flushes buffered data when indexing stops, sinks which store every write right away do nothing.
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    return bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+}
+
+/// Watermarks of sinks writing into directory, stored as json map name -> checkpoint.
+pub struct WatermarkFile {
+    path: PathBuf,
+}
+
+impl WatermarkFile {
+    pub fn new(dir: &Path) -> Self {
+        WatermarkFile { path: dir.join("_watermarks.json") }
+    }
+
+    pub fn read(&self) -> Result<BTreeMap<String, u64>> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        return Ok(serde_json::from_str(&fs::read_to_string(&self.path)?)?);
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<u64>> {
+        return Ok(self.read()?.get(name).cloned());
+    }
+
+    /// Updates given watermarks, file is replaced by rename so it is never half written.
+    pub fn store<'a>(&self, watermarks: impl IntoIterator<Item = (&'a String, &'a u64)>) -> Result<()> {
+        let mut stored = self.read()?;
+        stored.extend(watermarks.into_iter().map(|(name, checkpoint)| (name.clone(), *checkpoint)));
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string(&stored)?)?;
+        fs::rename(tmp, &self.path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use log::{debug, info};
+use crate::sink::relational::{event_rows, EventRow, SqlValue, EVENTS_TABLE};
+use crate::sink::{hex, CheckpointBatch, Sink, Watermark, WatermarkFile};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// One json object per event, all events in one stream.
+    Jsonl,
+    /// Decoded Scallop events with flattened columns (see `relational::scallop_row`), one stream per event type.
+    Csv,
+}
+
+enum Line {
+    Json(String),
+    Csv(EventRow),
+}
+
+struct OpenFile {
+    writer: BufWriter<File>,
+    bytes: u64,
+}
+
+#[derive(Default)]
+struct State {
+    files: HashMap<&'static str, OpenFile>,
+    /// Event type of the last csv row written to stdout, header is repeated when it changes.
+    last_table: Option<&'static str>,
+    /// Watermarks of stdout output, they are not persisted.
+    watermarks: BTreeMap<String, u64>,
+}
+
+/// Writes events as json lines or csv to stdout or to files in `dir`, meant for debugging and piping into `jq`.
+/// Files are named `events-{checkpoint}.jsonl` or `{event table}-{checkpoint}.csv` by their first checkpoint
+/// and rotated once they reach `max_bytes`, watermark is stored in the directory after every write.
+pub struct FileSink {
+    format: OutputFormat,
+    dir: Option<PathBuf>,
+    max_bytes: u64,
+    state: Mutex<State>,
+}
+
+impl FileSink {
+    pub fn stdout(format: OutputFormat) -> Self {
+        FileSink { format, dir: None, max_bytes: u64::MAX, state: Mutex::new(State::default()) }
+    }
+
+    pub fn open(format: OutputFormat, dir: &str, max_bytes: u64) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        info!("writing {:?} files into {}, rotating after {} bytes", format, dir, max_bytes);
+        Ok(FileSink { format, dir: Some(PathBuf::from(dir)), max_bytes, state: Mutex::new(State::default()) })
+    }
+
+    /// Lines of batch with name of their stream.
+    fn lines(&self, batch: &[CheckpointBatch]) -> Vec<(&'static str, Line)> {
+        let mut lines = vec![];
+        for checkpoint in batch.iter() {
+            for event in checkpoint.events.iter() {
+                match self.format {
+                    OutputFormat::Jsonl => {
+                        lines.push(("events", Line::Json(event.json(checkpoint).to_string())));
+                    }
+                    OutputFormat::Csv => {
+                        lines.extend(event_rows(checkpoint, event).into_iter()
+                            .filter(|row| row.table != EVENTS_TABLE)
+                            .map(|row| (row.table, Line::Csv(row))));
+                    }
+                }
+            }
+        }
+        return lines;
+    }
+
+    fn render(line: &Line) -> String {
+        match line {
+            Line::Json(line) => format!("{}\n", line),
+            Line::Csv(row) => {
+                let values: Vec<String> = row.columns.iter().map(|(_, value)| csv_field(&csv_value(value))).collect();
+                format!("{}\n", values.join(","))
+            }
+        }
+    }
+
+    fn header(line: &Line) -> Option<String> {
+        match line {
+            Line::Json(_) => None,
+            Line::Csv(row) => {
+                let names: Vec<&str> = row.columns.iter().map(|(name, _)| *name).collect();
+                Some(format!("{}\n", names.join(",")))
+            }
+        }
+    }
+
+    fn write_stdout(&self, state: &mut State, lines: Vec<(&'static str, Line)>) -> Result<()> {
+        let mut stdout = std::io::stdout().lock();
+        for (stream, line) in lines {
+            if state.last_table != Some(stream) {
+                if let Some(header) = Self::header(&line) {
+                    stdout.write_all(header.as_bytes())?;
+                }
+                state.last_table = Some(stream);
+            }
+            stdout.write_all(Self::render(&line).as_bytes())?;
+        }
+        stdout.flush()?;
+        Ok(())
+    }
+
+    fn write_files(&self, state: &mut State, checkpoint: u64, lines: Vec<(&'static str, Line)>) -> Result<()> {
+        let dir = self.dir.as_ref().ok_or(anyhow!("output directory is not set"))?;
+        for (stream, line) in lines {
+            if !state.files.contains_key(stream) {
+                let extension = match self.format {
+                    OutputFormat::Jsonl => "jsonl",
+                    OutputFormat::Csv => "csv",
+                };
+                let path = dir.join(format!("{}-{}.{}", stream, checkpoint, extension));
+                debug!("opening {:?}", path);
+                let mut file = OpenFile { writer: BufWriter::new(File::create(path)?), bytes: 0 };
+                if let Some(header) = Self::header(&line) {
+                    file.writer.write_all(header.as_bytes())?;
+                    file.bytes += header.len() as u64;
+                }
+                state.files.insert(stream, file);
+            }
+            let file = state.files.get_mut(stream).unwrap();
+            let line = Self::render(&line);
+            file.writer.write_all(line.as_bytes())?;
+            file.bytes += line.len() as u64;
+        }
+        for file in state.files.values_mut() {
+            file.writer.flush()?;
+        }
+        // full files are closed, next event of the stream starts new file
+        let max_bytes = self.max_bytes;
+        state.files.retain(|_, file| file.bytes < max_bytes);
+        Ok(())
+    }
+}
+
+fn csv_value(value: &SqlValue) -> String {
+    match value {
+        SqlValue::Text(value) | SqlValue::Json(value) => value.clone(),
+        SqlValue::Numeric(value) => value.to_string(),
+        SqlValue::BigInt(value) => value.to_string(),
+        SqlValue::Bool(value) => value.to_string(),
+        SqlValue::Bytes(value) => hex(value),
+    }
+}
+
+/// Quotes field when needed, eg. type names with more type parameters contain commas.
+fn csv_field(value: &str) -> String {
+    if value.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        return format!("\"{}\"", value.replace('"', "\"\""));
+    }
+    return value.to_string();
+}
+
+#[async_trait]
+impl Sink for FileSink {
+    async fn write(&self, batch: &[CheckpointBatch], watermark: &Watermark) -> Result<()> {
+        let lines = self.lines(batch);
+        let first = batch.first().map(|checkpoint| checkpoint.checkpoint).unwrap_or(watermark.checkpoint);
+        let mut state = self.state.lock().map_err(|_| anyhow!("output state poisoned"))?;
+        match &self.dir {
+            Some(dir) => {
+                self.write_files(&mut state, first, lines)?;
+                WatermarkFile::new(dir).store([(&watermark.name, &watermark.checkpoint)])?;
+            }
+            None => {
+                self.write_stdout(&mut state, lines)?;
+                state.watermarks.insert(watermark.name.clone(), watermark.checkpoint);
+            }
+        }
+        Ok(())
+    }
+
+    async fn watermark(&self, name: &str) -> Result<Option<u64>> {
+        if let Some(dir) = &self.dir {
+            return WatermarkFile::new(dir).get(name);
+        }
+        let state = self.state.lock().map_err(|_| anyhow!("output state poisoned"))?;
+        return Ok(state.watermarks.get(name).cloned());
+    }
+}
@@ -0,0 +1,160 @@
+use std::time::Duration;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use rdkafka::admin::{AdminClient, AdminOptions, NewTopic, TopicReplication};
+use rdkafka::client::DefaultClientContext;
+use rdkafka::consumer::{BaseConsumer, Consumer};
+use rdkafka::error::RDKafkaErrorCode;
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
+use rdkafka::util::Timeout;
+use rdkafka::{ClientConfig, Message, Offset, TopicPartitionList};
+use tokio::sync::Mutex;
+use crate::sink::{CheckpointBatch, Sink, Watermark};
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+/// Time message waits for space in full producer queue, write fails (and is retried by pipeline) after it.
+const QUEUE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Kafka connection settings, works with any broker speaking Kafka protocol (eg. Redpanda).
+#[derive(Debug, Clone)]
+pub struct KafkaOptions {
+    pub brokers: String,
+    /// Events go to `{prefix}{event name}`, undecodable events to `{prefix}dead_letters`
+    /// and watermarks to compacted `{prefix}watermarks`.
+    pub topic_prefix: String,
+    /// Has to be stable across restarts, so unfinished transaction of previous run is aborted.
+    pub transactional_id: String,
+}
+
+/// Publishes every event as json (see `IndexedEvent::json`) to topic of its event type, keyed by obligation
+/// (digest for events without obligation) so events of one obligation stay in order.
+/// Events of a write and watermark message are sent in one transaction, consumers with
+/// `isolation.level=read_committed` never see events of checkpoints which are not committed.
+pub struct KafkaSink {
+    options: KafkaOptions,
+    producer: FutureProducer,
+    /// Transactions of one producer can not overlap.
+    transaction: Mutex<()>,
+}
+
+impl KafkaSink {
+    pub async fn connect(options: KafkaOptions) -> Result<Self> {
+        info!("connecting to kafka {}", options.brokers);
+        Self::create_watermark_topic(&options).await?;
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", &options.brokers)
+            .set("transactional.id", &options.transactional_id)
+            .set("enable.idempotence", "true")
+            .create()?;
+        let init = producer.clone();
+        tokio::task::spawn_blocking(move || init.init_transactions(TIMEOUT)).await??;
+        info!("preparing kafka done");
+        Ok(KafkaSink { options, producer, transaction: Mutex::new(()) })
+    }
+
+    fn watermark_topic(options: &KafkaOptions) -> String {
+        return format!("{}watermarks", options.topic_prefix);
+    }
+
+    async fn create_watermark_topic(options: &KafkaOptions) -> Result<()> {
+        let admin: AdminClient<DefaultClientContext> = ClientConfig::new().set("bootstrap.servers", &options.brokers).create()?;
+        let topic = Self::watermark_topic(options);
+        // one partition, so the last message of watermark is its current value
+        let new_topic = NewTopic::new(&topic, 1, TopicReplication::Fixed(-1)).set("cleanup.policy", "compact");
+        for result in admin.create_topics(&[new_topic], &AdminOptions::new()).await? {
+            match result {
+                Ok(_) => info!("created topic {}", topic),
+                Err((_, RDKafkaErrorCode::TopicAlreadyExists)) => {}
+                Err((topic, err)) => return Err(anyhow!("failed to create topic {}: {}", topic, err)),
+            }
+        }
+        Ok(())
+    }
+
+    async fn send(&self, batch: &[CheckpointBatch], watermark: &Watermark) -> Result<()> {
+        let mut messages = vec![];
+        for checkpoint in batch.iter() {
+            for event in checkpoint.events.iter() {
+                let (topic, key) = match &event.decoded {
+                    Ok(decoded) => (
+                        format!("{}{}", self.options.topic_prefix, decoded.name),
+                        decoded.obligation.clone().unwrap_or(event.data.digest.clone()),
+                    ),
+                    Err(err) => {
+                        warn!("failed to decode {}: {}", event.key(), err);
+                        (format!("{}dead_letters", self.options.topic_prefix), event.data.digest.clone())
+                    }
+                };
+                messages.push((topic, key, event.json(checkpoint).to_string()));
+            }
+        }
+        debug!("sending {} messages for checkpoint {}", messages.len(), watermark.checkpoint);
+        let deliveries = messages.iter().map(|(topic, key, payload)| {
+            self.producer.send(FutureRecord::to(topic).key(key).payload(payload), Timeout::After(QUEUE_TIMEOUT))
+        });
+        for delivery in futures::future::join_all(deliveries).await {
+            delivery.map_err(|(err, _)| err)?;
+        }
+        let topic = Self::watermark_topic(&self.options);
+        let checkpoint = watermark.checkpoint.to_string();
+        self.producer.send(FutureRecord::to(&topic).key(&watermark.name).payload(&checkpoint), Timeout::After(QUEUE_TIMEOUT))
+            .await
+            .map_err(|(err, _)| err)?;
+        Ok(())
+    }
+
+    fn read_watermark(options: &KafkaOptions, name: &str) -> Result<Option<u64>> {
+        let consumer: BaseConsumer = ClientConfig::new()
+            .set("bootstrap.servers", &options.brokers)
+            .set("group.id", format!("{}-watermark", options.transactional_id))
+            .set("enable.auto.commit", "false")
+            .set("isolation.level", "read_committed")
+            .create()?;
+        let topic = Self::watermark_topic(options);
+        let (low, high) = consumer.fetch_watermarks(&topic, 0, TIMEOUT)?;
+        if high <= low {
+            return Ok(None);
+        }
+        let mut partitions = TopicPartitionList::new();
+        partitions.add_partition_offset(&topic, 0, Offset::Beginning)?;
+        consumer.assign(&partitions)?;
+        let mut watermark = None;
+        // transaction markers are not delivered, so end of topic is recognized by poll timeout as well
+        while let Some(message) = consumer.poll(Duration::from_secs(1)) {
+            let message = message?;
+            if message.key() == Some(name.as_bytes()) {
+                let payload = message.payload().ok_or(anyhow!("empty watermark message"))?;
+                watermark = Some(std::str::from_utf8(payload)?.parse::<u64>()?);
+            }
+            if message.offset() + 1 >= high {
+                break;
+            }
+        }
+        return Ok(watermark);
+    }
+}
+
+#[async_trait]
+impl Sink for KafkaSink {
+    async fn write(&self, batch: &[CheckpointBatch], watermark: &Watermark) -> Result<()> {
+        let _transaction = self.transaction.lock().await;
+        self.producer.begin_transaction()?;
+        if let Err(err) = self.send(batch, watermark).await {
+            let producer = self.producer.clone();
+            if let Err(abort) = tokio::task::spawn_blocking(move || producer.abort_transaction(TIMEOUT)).await? {
+                warn!("failed to abort kafka transaction: {:?}", abort);
+            }
+            return Err(err);
+        }
+        let producer = self.producer.clone();
+        tokio::task::spawn_blocking(move || producer.commit_transaction(TIMEOUT)).await??;
+        Ok(())
+    }
+
+    async fn watermark(&self, name: &str) -> Result<Option<u64>> {
+        let options = self.options.clone();
+        let name = name.to_string();
+        return tokio::task::spawn_blocking(move || Self::read_watermark(&options, &name)).await?;
+    }
+}
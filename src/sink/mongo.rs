@@ -0,0 +1,312 @@
+use std::collections::BTreeMap;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use log::{debug, info, warn};
+use mongodb::bson::{doc, spec::BinarySubtype, Binary, Bson, Document};
+use mongodb::options::{FindOneOptions, IndexOptions, UpdateOptions};
+use mongodb::{Client, Database, IndexModel};
+use crate::market::MarketConfig;
+use crate::objects::ObjectSnapshot;
+use crate::obligation::ObligationState;
+use crate::prices::PricePoint;
+use crate::sink::{CheckpointBatch, IndexedEvent, Sink, Watermark};
+
+/// Collection with one document per indexed event.
+pub const EVENTS_COLLECTION: &str = "events";
+/// Collection with watermarks, `_id` is watermark name.
+pub const WATERMARKS_COLLECTION: &str = "watermarks";
+/// Collection with tracked obligation positions, `_id` is obligation id.
+pub const OBLIGATIONS_COLLECTION: &str = "obligations";
+/// Collection with the latest version of indexed objects, `_id` is object id.
+pub const OBJECTS_COLLECTION: &str = "objects";
+/// Collection with oracle price updates, `_id` is `{asset}:{checkpoint}`.
+pub const PRICES_COLLECTION: &str = "prices";
+/// Collection with market configurations, `_id` is `{asset}:{checkpoint}`, `config` is json of `MarketConfig`.
+pub const MARKET_CONFIGS_COLLECTION: &str = "market_configs";
+/// Upsert of document with newer version fails on unique `_id`, the stored document is kept.
+const DUPLICATE_KEY: i32 = 11000;
+/// Maximum number of upserts sent in one `update` command.
+const UPSERT_CHUNK: usize = 1000;
+
+/// Stores each `IndexerData` with its decoded event as document of `events` collection,
+/// documents are upserted by (digest, index) so replayed checkpoints do not create duplicates.
+/// Tracked obligation positions are replaced in `obligations` collection, indexed objects in `objects`
+/// collection, oracle price updates are upserted into `prices` collection and market configurations into `market_configs`
+/// collection, watermark is moved after all documents of the batch are stored.
+pub struct MongoSink {
+    pub database: Database,
+}
+
+impl MongoSink {
+    /// Connects to `uri` (eg. `mongodb://localhost:27017`) and creates indexes of `events`, `objects`, `prices`
+    /// and `market_configs` collections.
+    pub async fn connect(uri: &str, database: &str) -> Result<Self> {
+        info!("connecting to mongodb, database {}", database);
+        let client = Client::with_uri_str(uri).await?;
+        let sink = MongoSink { database: client.database(database) };
+        sink.create_indexes().await?;
+        info!("preparing mongodb done");
+        Ok(sink)
+    }
+
+    pub async fn create_indexes(&self) -> Result<()> {
+        let unique = IndexOptions::builder().unique(true).build();
+        let indexes = vec![
+            IndexModel::builder().keys(doc! {"digest": 1, "index": 1}).options(unique).build(),
+            IndexModel::builder().keys(doc! {"obligation": 1}).build(),
+            IndexModel::builder().keys(doc! {"type": 1, "checkpoint": 1}).build(),
+            IndexModel::builder().keys(doc! {"checkpoint": 1}).build(),
+            IndexModel::builder().keys(doc! {"sender": 1}).build(),
+        ];
+        self.database.collection::<Document>(EVENTS_COLLECTION).create_indexes(indexes, None).await?;
+        let indexes = vec![
+            IndexModel::builder().keys(doc! {"obligation": 1}).build(),
+            IndexModel::builder().keys(doc! {"type": 1}).build(),
+        ];
+        self.database.collection::<Document>(OBJECTS_COLLECTION).create_indexes(indexes, None).await?;
+        let index = IndexModel::builder().keys(doc! {"asset": 1, "checkpoint": -1}).build();
+        self.database.collection::<Document>(PRICES_COLLECTION).create_index(index, None).await?;
+        let index = IndexModel::builder().keys(doc! {"asset": 1, "checkpoint": -1}).build();
+        self.database.collection::<Document>(MARKET_CONFIGS_COLLECTION).create_index(index, None).await?;
+        Ok(())
+    }
+
+    fn document(checkpoint: &CheckpointBatch, event: &IndexedEvent) -> Document {
+        let data = &event.data;
+        let mut document = doc! {
+            "digest": &data.digest,
+            "index": data.index as i64,
+            "checkpoint": data.checkpoint as i64,
+            "epoch": data.epoch as i64,
+            "timestamp_ms": checkpoint.timestamp_ms as i64,
+            "sender": &data.sender,
+            "type": &data.type_,
+            "data": Binary { subtype: BinarySubtype::Generic, bytes: data.data.clone() },
+        };
+        match &event.decoded {
+            Ok(decoded) => {
+                document.insert("name", &decoded.name);
+                document.insert("obligation", decoded.obligation.clone().map(Bson::String).unwrap_or(Bson::Null));
+                // bson has no u64, amounts above i64::MAX can not be stored as numbers
+                match mongodb::bson::to_bson(&decoded.event) {
+                    Ok(value) => {
+                        document.insert("event", value);
+                    }
+                    Err(err) => {
+                        warn!("failed to convert {} to bson: {}", event.key(), err);
+                        document.insert("error", err.to_string());
+                    }
+                }
+                let prices = event.prices(checkpoint);
+                if !prices.is_empty() {
+                    let prices: Document = prices.into_iter().map(|(asset, value)| (asset, Bson::String(value.to_string()))).collect();
+                    document.insert("prices", prices);
+                }
+            }
+            Err(err) => {
+                warn!("failed to decode {}: {}", event.key(), err);
+                document.insert("error", err.to_string());
+            }
+        }
+        return document;
+    }
+
+    fn object_document(snapshot: &ObjectSnapshot) -> Document {
+        let mut document = doc! {
+            "version": snapshot.version as i64,
+            "type": &snapshot.type_,
+            "owner": &snapshot.owner,
+            "checkpoint": snapshot.checkpoint as i64,
+            "digest": &snapshot.digest,
+            "deleted": snapshot.deleted,
+            "data": Binary { subtype: BinarySubtype::Generic, bytes: snapshot.data.clone() },
+            "obligation": snapshot.obligation.clone().map(Bson::String).unwrap_or(Bson::Null),
+        };
+        match &snapshot.decoded {
+            Ok(decoded) => match mongodb::bson::to_bson(decoded) {
+                Ok(value) => {
+                    document.insert("object", value);
+                }
+                Err(err) => {
+                    warn!("failed to convert object {} to bson: {}", snapshot.id, err);
+                    document.insert("error", err.to_string());
+                }
+            },
+            Err(err) => {
+                document.insert("error", err.to_string());
+            }
+        }
+        return document;
+    }
+
+    /// Amounts are strings, bson has no u64.
+    fn amounts_document(amounts: &BTreeMap<String, u64>) -> Vec<Document> {
+        return amounts.iter().map(|(asset, amount)| doc! {"asset": asset, "amount": amount.to_string()}).collect();
+    }
+
+    fn parse_amounts(document: &Document, field: &str) -> Result<BTreeMap<String, u64>> {
+        let mut amounts = BTreeMap::new();
+        for amount in document.get_array(field)?.iter() {
+            let amount = amount.as_document().ok_or(anyhow!("invalid {} of obligation", field))?;
+            amounts.insert(amount.get_str("asset")?.to_string(), amount.get_str("amount")?.parse::<u64>()?);
+        }
+        return Ok(amounts);
+    }
+
+    /// Values are strings, bson has no u64.
+    fn price_document(point: &PricePoint) -> Document {
+        return doc! {
+            "asset": &point.asset,
+            "checkpoint": point.checkpoint as i64,
+            "timestamp_ms": point.timestamp_ms as i64,
+            "value": point.value.to_string(),
+            "last_updated": point.last_updated.to_string(),
+        };
+    }
+
+    fn parse_price(document: &Document) -> Result<PricePoint> {
+        return Ok(PricePoint {
+            asset: document.get_str("asset")?.to_string(),
+            checkpoint: document.get_i64("checkpoint")? as u64,
+            timestamp_ms: document.get_i64("timestamp_ms")? as u64,
+            value: document.get_str("value")?.parse()?,
+            last_updated: document.get_str("last_updated")?.parse()?,
+        });
+    }
+
+    /// Sends `update` command with upserts in chunks, duplicate key errors are ignored with `skip_duplicates`.
+    async fn upsert(&self, collection: &str, updates: Vec<Document>, skip_duplicates: bool) -> Result<()> {
+        // driver 2.x has no bulk write api, `update` command with many statements is one round trip
+        for chunk in updates.chunks(UPSERT_CHUNK) {
+            let command = doc! {"update": collection, "updates": chunk.to_vec(), "ordered": false};
+            let response = self.database.run_command(command, None).await?;
+            if let Ok(errors) = response.get_array("writeErrors") {
+                let errors: Vec<&Bson> = errors.iter()
+                    .filter(|error| !skip_duplicates || error.as_document().and_then(|error| error.get_i32("code").ok()) != Some(DUPLICATE_KEY))
+                    .collect();
+                if !errors.is_empty() {
+                    return Err(anyhow!("failed to upsert {} documents into {}: {:?}", errors.len(), collection, errors.first()));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Sink for MongoSink {
+    async fn write(&self, batch: &[CheckpointBatch], watermark: &Watermark) -> Result<()> {
+        let updates: Vec<Document> = batch.iter()
+            .flat_map(|checkpoint| checkpoint.events.iter().map(move |event| (checkpoint, event)))
+            .map(|(checkpoint, event)| doc! {
+                "q": {"digest": &event.data.digest, "index": event.data.index as i64},
+                "u": {"$set": Self::document(checkpoint, event)},
+                "upsert": true,
+            })
+            .collect();
+        debug!("upserting {} events for checkpoint {}", updates.len(), watermark.checkpoint);
+        self.upsert(EVENTS_COLLECTION, updates, false).await?;
+        // updates are unordered, so only the newest position of obligation in the batch is sent
+        let states: BTreeMap<&String, &ObligationState> = batch.iter()
+            .flat_map(|checkpoint| checkpoint.obligations.iter())
+            .map(|state| (&state.id, state))
+            .collect();
+        let positions: Vec<Document> = states.values()
+            .map(|state| doc! {
+                "q": {"_id": &state.id},
+                "u": {"$set": {
+                    "checkpoint": state.checkpoint as i64,
+                    "collaterals": Self::amounts_document(&state.collaterals),
+                    "debts": Self::amounts_document(&state.debts),
+                }},
+                "upsert": true,
+            })
+            .collect();
+        self.upsert(OBLIGATIONS_COLLECTION, positions, false).await?;
+        let mut snapshots: BTreeMap<&String, &ObjectSnapshot> = BTreeMap::new();
+        for snapshot in batch.iter().flat_map(|checkpoint| checkpoint.objects.iter()) {
+            if snapshots.get(&snapshot.id).map_or(true, |stored| stored.version < snapshot.version) {
+                snapshots.insert(&snapshot.id, snapshot);
+            }
+        }
+        // stored newer version does not match the filter, upsert then fails on duplicate `_id`
+        let objects: Vec<Document> = snapshots.values()
+            .map(|snapshot| doc! {
+                "q": {"_id": &snapshot.id, "version": {"$lt": snapshot.version as i64}},
+                "u": Self::object_document(snapshot),
+                "upsert": true,
+            })
+            .collect();
+        self.upsert(OBJECTS_COLLECTION, objects, true).await?;
+        let prices: Vec<Document> = batch.iter()
+            .flat_map(|checkpoint| checkpoint.price_updates.iter())
+            .map(|point| doc! {
+                "q": {"_id": format!("{}:{}", point.asset, point.checkpoint)},
+                "u": {"$set": Self::price_document(point)},
+                "upsert": true,
+            })
+            .collect();
+        self.upsert(PRICES_COLLECTION, prices, false).await?;
+        let mut configs: Vec<Document> = vec![];
+        for config in batch.iter().flat_map(|checkpoint| checkpoint.market_configs.iter()) {
+            let json = serde_json::to_string(config)?;
+            configs.push(doc! {
+                "q": {"_id": format!("{}:{}", config.asset, config.checkpoint)},
+                "u": {"$set": {"asset": &config.asset, "checkpoint": config.checkpoint as i64, "config": json}},
+                "upsert": true,
+            });
+        }
+        self.upsert(MARKET_CONFIGS_COLLECTION, configs, false).await?;
+        self.database.collection::<Document>(WATERMARKS_COLLECTION).update_one(
+            doc! {"_id": &watermark.name},
+            doc! {"$set": {"checkpoint": watermark.checkpoint as i64}},
+            UpdateOptions::builder().upsert(true).build(),
+        ).await?;
+        Ok(())
+    }
+
+    async fn watermark(&self, name: &str) -> Result<Option<u64>> {
+        let watermark = self.database.collection::<Document>(WATERMARKS_COLLECTION).find_one(doc! {"_id": name}, None).await?;
+        return Ok(watermark.and_then(|watermark| watermark.get_i64("checkpoint").ok()).map(|checkpoint| checkpoint as u64));
+    }
+
+    async fn obligations(&self, ids: &[String]) -> Result<Vec<ObligationState>> {
+        let documents: Vec<Document> = self.database.collection::<Document>(OBLIGATIONS_COLLECTION)
+            .find(doc! {"_id": {"$in": ids.to_vec()}}, None).await?
+            .try_collect().await?;
+        return documents.iter()
+            .map(|document| Ok(ObligationState {
+                id: document.get_str("_id")?.to_string(),
+                collaterals: Self::parse_amounts(document, "collaterals")?,
+                debts: Self::parse_amounts(document, "debts")?,
+                checkpoint: document.get_i64("checkpoint")? as u64,
+            }))
+            .collect();
+    }
+
+    async fn latest_prices(&self, assets: &[String], checkpoint: u64) -> Result<Vec<PricePoint>> {
+        let collection = self.database.collection::<Document>(PRICES_COLLECTION);
+        let mut points = vec![];
+        for asset in assets.iter() {
+            let options = FindOneOptions::builder().sort(doc! {"checkpoint": -1}).build();
+            let document = collection.find_one(doc! {"asset": asset, "checkpoint": {"$lte": checkpoint as i64}}, options).await?;
+            if let Some(document) = document {
+                points.push(Self::parse_price(&document)?);
+            }
+        }
+        return Ok(points);
+    }
+    async fn market_configs(&self, assets: &[String], checkpoint: u64) -> Result<Vec<MarketConfig>> {
+        let collection = self.database.collection::<Document>(MARKET_CONFIGS_COLLECTION);
+        let mut configs = vec![];
+        for asset in assets.iter() {
+            let options = FindOneOptions::builder().sort(doc! {"checkpoint": -1}).build();
+            let document = collection.find_one(doc! {"asset": asset, "checkpoint": {"$lte": checkpoint as i64}}, options).await?;
+            if let Some(document) = document {
+                configs.push(serde_json::from_str(document.get_str("config")?)?);
+            }
+        }
+        return Ok(configs);
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use anyhow::{anyhow, Result};
+use arrow_array::{ArrayRef, BinaryArray, BooleanArray, Int64Array, RecordBatch, StringArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema};
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use serde::{Deserialize, Serialize};
+use crate::sink::relational::{event_rows, EventRow, SqlValue, EVENTS_TABLE};
+use crate::sink::{CheckpointBatch, Sink, Watermark, WatermarkFile};
+
+/// Suffix of files which are still written, they are not part of the export until renamed.
+const IN_PROGRESS_SUFFIX: &str = ".inprogress";
+/// Manifest of roll which is renaming closed files.
+const ROLL_FILE: &str = "_roll.json";
+
+struct OpenFile {
+    path: PathBuf,
+    writer: ArrowWriter<File>,
+    rows: usize,
+}
+
+/// Closed files (relative to export directory, with `.inprogress` suffix) and watermarks covered by them.
+/// Stored before the files are renamed, so roll interrupted by crash is finished on start.
+#[derive(Serialize, Deserialize, Debug)]
+struct RollManifest {
+    files: Vec<PathBuf>,
+    watermarks: BTreeMap<String, u64>,
+}
+
+impl RollManifest {
+    fn read(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(ROLL_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        return Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?));
+    }
+
+    /// Stores manifest, file is replaced by rename so it is never half written.
+    fn store(&self, dir: &Path) -> Result<()> {
+        let tmp = dir.join(format!("{}.tmp", ROLL_FILE));
+        fs::write(&tmp, serde_json::to_string(self)?)?;
+        fs::rename(tmp, dir.join(ROLL_FILE))?;
+        Ok(())
+    }
+
+    /// Renames files which are not renamed yet, stores watermarks and removes manifest.
+    fn apply(&self, dir: &Path) -> Result<()> {
+        for file in self.files.iter() {
+            let path = dir.join(file);
+            if path.exists() {
+                let path = path.to_string_lossy();
+                fs::rename(path.as_ref(), path.trim_end_matches(IN_PROGRESS_SUFFIX))?;
+            }
+        }
+        if !self.watermarks.is_empty() {
+            WatermarkFile::new(dir).store(self.watermarks.iter())?;
+        }
+        fs::remove_file(dir.join(ROLL_FILE))?;
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct State {
+    files: HashMap<(&'static str, u64), OpenFile>,
+    epoch: Option<u64>,
+    /// Watermarks covered by data in open files, stored once the files are closed.
+    pending: BTreeMap<String, u64>,
+}
+
+/// Exports decoded Scallop events into `{dir}/{event table}/epoch={epoch}/part-{checkpoint}.parquet`,
+/// columns are the same as in relational sinks (see `relational::scallop_row`) with u64 amounts as UInt64.
+/// Events of other protocols and events which could not be decoded are not exported.
+///
+/// Files are written with `.inprogress` suffix and renamed once they have `max_rows` rows or epoch ends,
+/// then all open files are closed and watermark is stored. Closed files and their watermark are recorded in
+/// `_roll.json` before renaming, roll interrupted by crash is finished on start and unfinished files are removed,
+/// so after restart checkpoints since the last stored watermark are exported again without duplicates.
+/// Source has to be able to provide those checkpoints again, eg. remote archive or backfill, files removed
+/// from local ingestion directory are lost, so the indexer rejects parquet sink with local source.
+/// Directory should be written by one indexer at a time.
+pub struct ParquetSink {
+    dir: PathBuf,
+    max_rows: usize,
+    state: Arc<Mutex<State>>,
+}
+
+impl ParquetSink {
+    pub fn open(dir: &str, max_rows: usize) -> Result<Self> {
+        let dir = PathBuf::from(dir);
+        fs::create_dir_all(&dir)?;
+        if let Some(manifest) = RollManifest::read(&dir)? {
+            warn!("finishing interrupted roll of {} files", manifest.files.len());
+            manifest.apply(&dir)?;
+        }
+        Self::remove_in_progress(&dir)?;
+        info!("exporting parquet files into {:?}, {} rows per file", dir, max_rows);
+        Ok(ParquetSink { dir, max_rows: max_rows.max(1), state: Arc::new(Mutex::new(State::default())) })
+    }
+
+    fn remove_in_progress(dir: &Path) -> Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                Self::remove_in_progress(&path)?;
+            } else if path.to_string_lossy().ends_with(IN_PROGRESS_SUFFIX) {
+                warn!("removing unfinished file {:?}", path);
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Closes (renames) all open files and stores watermarks of their data.
+    fn roll(dir: &Path, state: &mut State) -> Result<()> {
+        let mut files = vec![];
+        for (_, file) in state.files.drain() {
+            debug!("closing {:?} with {} rows", file.path, file.rows);
+            file.writer.close()?;
+            files.push(file.path.strip_prefix(dir)?.to_path_buf());
+        }
+        if files.is_empty() && state.pending.is_empty() {
+            return Ok(());
+        }
+        let manifest = RollManifest { files, watermarks: std::mem::take(&mut state.pending) };
+        manifest.store(dir)?;
+        return manifest.apply(dir);
+    }
+
+    fn record_batch(rows: &[EventRow]) -> Result<RecordBatch> {
+        let first = rows.first().ok_or(anyhow!("no rows"))?;
+        let mut fields = vec![];
+        let mut arrays: Vec<ArrayRef> = vec![];
+        for (i, (name, value)) in first.columns.iter().enumerate() {
+            let values = rows.iter().map(|row| &row.columns[i].1);
+            let (data_type, array): (DataType, ArrayRef) = match value {
+                SqlValue::Text(_) | SqlValue::Json(_) => (DataType::Utf8, Arc::new(values.map(|value| match value {
+                    SqlValue::Text(value) | SqlValue::Json(value) => Some(value.as_str()),
+                    _ => None,
+                }).collect::<StringArray>())),
+                SqlValue::Numeric(_) => (DataType::UInt64, Arc::new(values.map(|value| match value {
+                    SqlValue::Numeric(value) => Some(*value),
+                    _ => None,
+                }).collect::<UInt64Array>())),
+                SqlValue::BigInt(_) => (DataType::Int64, Arc::new(values.map(|value| match value {
+                    SqlValue::BigInt(value) => Some(*value),
+                    _ => None,
+                }).collect::<Int64Array>())),
+                SqlValue::Bool(_) => (DataType::Boolean, Arc::new(values.map(|value| match value {
+                    SqlValue::Bool(value) => Some(*value),
+                    _ => None,
+                }).collect::<BooleanArray>())),
+                SqlValue::Bytes(_) => (DataType::Binary, Arc::new(values.map(|value| match value {
+                    SqlValue::Bytes(value) => Some(value.as_slice()),
+                    _ => None,
+                }).collect::<BinaryArray>())),
+            };
+            fields.push(Field::new(*name, data_type, false));
+            arrays.push(array);
+        }
+        return Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)?);
+    }
+
+    /// Appends rows of one checkpoint to open files, returns true when some file is full.
+    fn write_rows(dir: &Path, max_rows: usize, state: &mut State, epoch: u64, checkpoint: u64, rows: Vec<EventRow>) -> Result<bool> {
+        if state.epoch.is_some_and(|current| current != epoch) {
+            // files of finished epoch will not get more rows
+            Self::roll(dir, state)?;
+        }
+        state.epoch = Some(epoch);
+        let mut tables: BTreeMap<&'static str, Vec<EventRow>> = BTreeMap::new();
+        for row in rows {
+            tables.entry(row.table).or_default().push(row);
+        }
+        let mut full = false;
+        for (table, rows) in tables {
+            let batch = Self::record_batch(&rows)?;
+            if !state.files.contains_key(&(table, epoch)) {
+                let partition = dir.join(table).join(format!("epoch={}", epoch));
+                fs::create_dir_all(&partition)?;
+                let path = partition.join(format!("part-{}.parquet{}", checkpoint, IN_PROGRESS_SUFFIX));
+                let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+                let writer = ArrowWriter::try_new(File::create(&path)?, batch.schema(), Some(properties))?;
+                state.files.insert((table, epoch), OpenFile { path, writer, rows: 0 });
+            }
+            let file = state.files.get_mut(&(table, epoch)).unwrap();
+            file.writer.write(&batch)?;
+            file.rows += batch.num_rows();
+            full |= file.rows >= max_rows;
+        }
+        Ok(full)
+    }
+
+    /// Closes all open files, called when indexing stops.
+    pub fn finish(&self) -> Result<()> {
+        let mut state = self.state.lock().map_err(|_| anyhow!("parquet state poisoned"))?;
+        return Self::roll(&self.dir, &mut state);
+    }
+}
+
+#[async_trait]
+impl Sink for ParquetSink {
+    async fn write(&self, batch: &[CheckpointBatch], watermark: &Watermark) -> Result<()> {
+        let checkpoints: Vec<(u64, u64, Vec<EventRow>)> = batch.iter()
+            .map(|checkpoint| {
+                let rows = checkpoint.events.iter()
+                    .flat_map(|event| event_rows(checkpoint, event))
+                    .filter(|row| row.table != EVENTS_TABLE)
+                    .collect();
+                (checkpoint.epoch, checkpoint.checkpoint, rows)
+            })
+            .collect();
+        let dir = self.dir.clone();
+        let max_rows = self.max_rows;
+        let state = self.state.clone();
+        let watermark = watermark.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut state = state.lock().map_err(|_| anyhow!("parquet state poisoned"))?;
+            let mut full = false;
+            for (epoch, checkpoint, rows) in checkpoints {
+                if !rows.is_empty() {
+                    full |= Self::write_rows(&dir, max_rows, &mut state, epoch, checkpoint, rows)?;
+                }
+            }
+            state.pending.insert(watermark.name, watermark.checkpoint);
+            // without open files watermark is stored right away
+            if full || state.files.is_empty() {
+                Self::roll(&dir, &mut state)?;
+            }
+            Ok::<_, anyhow::Error>(())
+        }).await?
+    }
+
+    async fn watermark(&self, name: &str) -> Result<Option<u64>> {
+        return WatermarkFile::new(&self.dir).get(name);
+    }
+
+    async fn close(&self) -> Result<()> {
+        return self.finish();
+    }
+}
@@ -0,0 +1,203 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use tokio::sync::Mutex;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::{Client, NoTls, Transaction};
+use crate::market::MarketConfig;
+use crate::obligation::ObligationState;
+use crate::prices::PricePoint;
+use crate::sink::relational::{event_rows, fold_positions, insert_statement, market_config_row, object_row, object_upsert_statement, position_rows, price_row, EventRow, SqlValue};
+use crate::sink::{CheckpointBatch, Sink, Watermark};
+
+/// Schema migrations (version, sql), applied in order, applied versions are stored in `schema_migrations`.
+pub const MIGRATIONS: [(i32, &str); 7] = [
+    (1, include_str!("../../migrations/postgres/0001_init.sql")),
+    (2, include_str!("../../migrations/postgres/0002_event_sender.sql")),
+    (3, include_str!("../../migrations/postgres/0003_obligation_positions.sql")),
+    (4, include_str!("../../migrations/postgres/0004_objects.sql")),
+    (5, include_str!("../../migrations/postgres/0005_prices.sql")),
+    (6, include_str!("../../migrations/postgres/0006_market_config.sql")),
+    (7, include_str!("../../migrations/postgres/0007_market_config_history.sql")),
+];
+
+/// Stores events into postgres, one table per Scallop event type plus `events` table with all indexed
+/// events (see `migrations/postgres`), tracked obligation positions, latest versions of indexed objects, oracle prices
+/// and market configuration history.
+/// Each write is one transaction together with watermark.
+pub struct PostgresSink {
+    client: Mutex<Client>,
+}
+
+impl PostgresSink {
+    /// Connects with libpq style config, eg. `host=localhost user=postgres` or `postgresql://user@host/db`,
+    /// and applies missing migrations.
+    pub async fn connect(url: &str) -> Result<Self> {
+        info!("connecting to postgres");
+        let (client, connection) = tokio_postgres::connect(url, NoTls).await?;
+        tokio::spawn(async move {
+            if let Err(err) = connection.await {
+                warn!("postgres connection closed: {:?}", err);
+            }
+        });
+        let sink = PostgresSink { client: Mutex::new(client) };
+        sink.migrate().await?;
+        info!("preparing postgres done");
+        Ok(sink)
+    }
+
+    pub async fn migrate(&self) -> Result<()> {
+        let mut client = self.client.lock().await;
+        client.batch_execute("CREATE TABLE IF NOT EXISTS schema_migrations (version INTEGER PRIMARY KEY)").await?;
+        for (version, sql) in MIGRATIONS.iter() {
+            let trx = client.transaction().await?;
+            // lock serializes indexers migrating same database
+            trx.batch_execute("LOCK TABLE schema_migrations IN EXCLUSIVE MODE").await?;
+            if trx.query_opt("SELECT version FROM schema_migrations WHERE version = $1", &[version]).await?.is_some() {
+                continue;
+            }
+            info!("applying postgres migration {}", version);
+            trx.batch_execute(sql).await?;
+            trx.execute("INSERT INTO schema_migrations (version) VALUES ($1)", &[version]).await?;
+            trx.commit().await?;
+        }
+        Ok(())
+    }
+
+    /// u64 amounts are sent as text and cast in database, postgres has no unsigned types.
+    fn placeholder(n: usize, value: &SqlValue) -> String {
+        match value {
+            SqlValue::Numeric(_) => format!("${}::TEXT::NUMERIC", n),
+            SqlValue::Json(_) => format!("${}::TEXT::JSONB", n),
+            _ => format!("${}", n),
+        }
+    }
+
+    async fn insert(trx: &Transaction<'_>, row: &EventRow) -> Result<()> {
+        return Self::execute(trx, &insert_statement(row, Self::placeholder), row).await;
+    }
+
+    async fn execute(trx: &Transaction<'_>, sql: &str, row: &EventRow) -> Result<()> {
+        let statement = trx.prepare_cached(sql).await?;
+        let params: Vec<Box<dyn ToSql + Sync + Send>> = row.columns.iter().map(|(_, value)| Self::param(value)).collect();
+        let params: Vec<&(dyn ToSql + Sync)> = params.iter().map(|param| param.as_ref() as &(dyn ToSql + Sync)).collect();
+        trx.execute(&statement, &params).await?;
+        Ok(())
+    }
+
+    fn param(value: &SqlValue) -> Box<dyn ToSql + Sync + Send> {
+        match value {
+            SqlValue::Text(value) => Box::new(value.clone()),
+            SqlValue::Numeric(value) => Box::new(value.to_string()),
+            SqlValue::BigInt(value) => Box::new(*value),
+            SqlValue::Bool(value) => Box::new(*value),
+            SqlValue::Bytes(value) => Box::new(value.clone()),
+            SqlValue::Json(value) => Box::new(value.clone()),
+        }
+    }
+}
+
+#[async_trait]
+impl Sink for PostgresSink {
+    async fn write(&self, batch: &[CheckpointBatch], watermark: &Watermark) -> Result<()> {
+        let mut client = self.client.lock().await;
+        let trx = client.transaction().await?;
+        let mut rows = 0;
+        for checkpoint in batch.iter() {
+            for event in checkpoint.events.iter() {
+                if let Err(err) = &event.decoded {
+                    warn!("failed to decode {}: {}", event.key(), err);
+                }
+                for row in event_rows(checkpoint, event) {
+                    Self::insert(&trx, &row).await?;
+                    rows += 1;
+                }
+            }
+            for state in checkpoint.obligations.iter() {
+                trx.execute(
+                    "INSERT INTO obligations (obligation, checkpoint) VALUES ($1, $2) ON CONFLICT (obligation) DO UPDATE SET checkpoint = EXCLUDED.checkpoint",
+                    &[&state.id, &(state.checkpoint as i64)],
+                ).await?;
+                trx.execute("DELETE FROM obligation_positions WHERE obligation = $1", &[&state.id]).await?;
+                for row in position_rows(state) {
+                    Self::insert(&trx, &row).await?;
+                }
+            }
+            for snapshot in checkpoint.objects.iter() {
+                let row = object_row(snapshot);
+                Self::execute(&trx, &object_upsert_statement(&row, Self::placeholder), &row).await?;
+            }
+            for point in checkpoint.price_updates.iter() {
+                Self::insert(&trx, &price_row(point)).await?;
+            }
+            for config in checkpoint.market_configs.iter() {
+                Self::insert(&trx, &market_config_row(config)).await?;
+            }
+        }
+        trx.execute(
+            "INSERT INTO watermarks (name, checkpoint) VALUES ($1, $2) ON CONFLICT (name) DO UPDATE SET checkpoint = EXCLUDED.checkpoint",
+            &[&watermark.name, &(watermark.checkpoint as i64)],
+        ).await?;
+        debug!("writing {} rows for checkpoint {}", rows, watermark.checkpoint);
+        trx.commit().await?;
+        Ok(())
+    }
+
+    async fn watermark(&self, name: &str) -> Result<Option<u64>> {
+        let client = self.client.lock().await;
+        let row = client.query_opt("SELECT checkpoint FROM watermarks WHERE name = $1", &[&name]).await?;
+        return Ok(row.map(|row| row.get::<_, i64>(0) as u64));
+    }
+
+    async fn obligations(&self, ids: &[String]) -> Result<Vec<ObligationState>> {
+        let client = self.client.lock().await;
+        let rows = client.query(
+            "SELECT o.obligation, o.checkpoint, p.side, p.asset, p.amount::TEXT FROM obligations o \
+             LEFT JOIN obligation_positions p ON p.obligation = o.obligation WHERE o.obligation = ANY($1)",
+            &[&ids],
+        ).await?;
+        let mut positions = vec![];
+        for row in rows {
+            let side: Option<String> = row.get(2);
+            let position = match side {
+                Some(side) => Some((side, row.get::<_, String>(3), row.get::<_, String>(4).parse::<u64>()?)),
+                None => None,
+            };
+            positions.push((row.get::<_, String>(0), row.get::<_, i64>(1) as u64, position));
+        }
+        return Ok(fold_positions(positions));
+    }
+
+    async fn latest_prices(&self, assets: &[String], checkpoint: u64) -> Result<Vec<PricePoint>> {
+        let client = self.client.lock().await;
+        let rows = client.query(
+            "SELECT DISTINCT ON (asset) asset, checkpoint, timestamp_ms, value::TEXT, last_updated::TEXT FROM prices \
+             WHERE asset = ANY($1) AND checkpoint <= $2 ORDER BY asset, checkpoint DESC",
+            &[&assets, &(checkpoint as i64)],
+        ).await?;
+        let mut points = vec![];
+        for row in rows {
+            points.push(PricePoint {
+                asset: row.get(0),
+                checkpoint: row.get::<_, i64>(1) as u64,
+                timestamp_ms: row.get::<_, i64>(2) as u64,
+                value: row.get::<_, String>(3).parse()?,
+                last_updated: row.get::<_, String>(4).parse()?,
+            });
+        }
+        return Ok(points);
+    }
+    async fn market_configs(&self, assets: &[String], checkpoint: u64) -> Result<Vec<MarketConfig>> {
+        let client = self.client.lock().await;
+        let rows = client.query(
+            "SELECT DISTINCT ON (asset) config::TEXT FROM market_config \
+             WHERE asset = ANY($1) AND checkpoint <= $2 ORDER BY asset, checkpoint DESC",
+            &[&assets, &(checkpoint as i64)],
+        ).await?;
+        let mut configs = vec![];
+        for row in rows {
+            configs.push(serde_json::from_str(&row.get::<_, String>(0))?);
+        }
+        return Ok(configs);
+    }
+}
@@ -0,0 +1,410 @@
+use std::collections::BTreeMap;
+use crate::events::{InterestModel, RiskModel, ScallopEvent};
+use crate::market::MarketConfig;
+use crate::objects::{ObjectSnapshot, ScallopObject};
+use crate::obligation::ObligationState;
+use crate::prices::PricePoint;
+use crate::sink::{CheckpointBatch, IndexedEvent};
+
+/// Column value of relational schema, amounts are `Numeric` as u64 does not fit into signed bigint.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Text(String),
+    Numeric(u64),
+    BigInt(i64),
+    Bool(bool),
+    Bytes(Vec<u8>),
+    Json(String),
+}
+
+/// One row of relational schema, missing nullable columns are left out.
+#[derive(Debug, Clone)]
+pub struct EventRow {
+    pub table: &'static str,
+    pub columns: Vec<(&'static str, SqlValue)>,
+}
+
+/// Table with every indexed event, events which could not be decoded have `error` set.
+pub const EVENTS_TABLE: &str = "events";
+
+/// Tables with one row per `ScallopEvent` variant.
+pub const EVENT_TABLES: [&str; 20] = [
+    "borrow_events",
+    "borrow_events_v2",
+    "borrow_flash_loan_events",
+    "borrow_limit_changed_events",
+    "collateral_deposit_events",
+    "collateral_withdraw_events",
+    "interest_accrued_events",
+    "interest_model_added_events",
+    "interest_model_change_created_events",
+    "liquidate_events",
+    "mint_events",
+    "obligation_created_events",
+    "obligation_locked_events",
+    "obligation_unlocked_events",
+    "redeem_events",
+    "repay_events",
+    "repay_flash_loan_events",
+    "risk_model_added_events",
+    "risk_model_change_created_events",
+    "supply_limit_changed_events",
+];
+
+fn text(value: impl ToString) -> SqlValue {
+    SqlValue::Text(value.to_string())
+}
+
+/// Rows are keyed by (checkpoint, digest, event_index), epoch and timestamp are in every table
+/// so events can be selected by time without join.
+fn key_columns(batch: &CheckpointBatch, event: &IndexedEvent) -> Vec<(&'static str, SqlValue)> {
+    vec![
+        ("checkpoint", SqlValue::BigInt(batch.checkpoint as i64)),
+        ("digest", text(&event.data.digest)),
+        ("event_index", SqlValue::BigInt(event.data.index as i64)),
+        ("epoch", SqlValue::BigInt(batch.epoch as i64)),
+        ("timestamp_ms", SqlValue::BigInt(batch.timestamp_ms as i64)),
+    ]
+}
+
+/// Row of `events` table and row of typed table for Scallop events.
+pub fn event_rows(batch: &CheckpointBatch, event: &IndexedEvent) -> Vec<EventRow> {
+    let mut columns = key_columns(batch, event);
+    columns.push(("type", text(&event.data.type_)));
+    columns.push(("data", SqlValue::Bytes(event.data.data.clone())));
+    if !event.data.sender.is_empty() {
+        columns.push(("sender", text(&event.data.sender)));
+    }
+    let decoded = match &event.decoded {
+        Ok(decoded) => decoded,
+        Err(err) => {
+            columns.push(("error", text(err)));
+            return vec![EventRow { table: EVENTS_TABLE, columns }];
+        }
+    };
+    columns.push(("decoded", SqlValue::Json(serde_json::to_string(decoded).unwrap_or("null".to_string()))));
+    if let Some(obligation) = &decoded.obligation {
+        columns.push(("obligation", text(obligation)));
+    }
+    let prices = event.prices(batch);
+    if !prices.is_empty() {
+        columns.push(("prices", SqlValue::Json(serde_json::to_string(&prices).unwrap_or("null".to_string()))));
+    }
+    let mut rows = vec![EventRow { table: EVENTS_TABLE, columns }];
+    if let Some(scallop) = decoded.scallop() {
+        let mut row = scallop_row(scallop);
+        let mut columns = key_columns(batch, event);
+        columns.append(&mut row.columns);
+        row.columns = columns;
+        rows.push(row);
+    }
+    return rows;
+}
+
+/// Insert statement of row, `placeholder` renders n-th parameter (from 1) for the database.
+/// Rows which are already stored are skipped, so replayed checkpoints do not fail.
+pub fn insert_statement(row: &EventRow, placeholder: impl Fn(usize, &SqlValue) -> String) -> String {
+    let names: Vec<&str> = row.columns.iter().map(|(name, _)| *name).collect();
+    let values: Vec<String> = row.columns.iter().enumerate().map(|(i, (_, value))| placeholder(i + 1, value)).collect();
+    return format!("INSERT INTO {} ({}) VALUES ({}) ON CONFLICT DO NOTHING", row.table, names.join(", "), values.join(", "));
+}
+
+/// Table with the latest version of each indexed object.
+pub const OBJECTS_TABLE: &str = "objects";
+
+/// Row of `objects` table, debt and collateral entries have their asset and amount in columns.
+pub fn object_row(snapshot: &ObjectSnapshot) -> EventRow {
+    let mut columns = vec![
+        ("object_id", text(&snapshot.id)),
+        ("version", SqlValue::BigInt(snapshot.version as i64)),
+        ("type", text(&snapshot.type_)),
+        ("owner", text(&snapshot.owner)),
+        ("checkpoint", SqlValue::BigInt(snapshot.checkpoint as i64)),
+        ("digest", text(&snapshot.digest)),
+        ("deleted", SqlValue::Bool(snapshot.deleted)),
+        ("data", SqlValue::Bytes(snapshot.data.clone())),
+    ];
+    match &snapshot.decoded {
+        Ok(Some(decoded)) => columns.push(("decoded", SqlValue::Json(serde_json::to_string(decoded).unwrap_or("null".to_string())))),
+        Ok(None) => {}
+        Err(err) => columns.push(("error", text(err))),
+    }
+    if let Some(obligation) = &snapshot.obligation {
+        columns.push(("obligation", text(obligation)));
+    }
+    match snapshot.scallop() {
+        Some(ScallopObject::Debt { asset, amount, .. }) | Some(ScallopObject::Collateral { asset, amount }) => {
+            columns.push(("asset", text(asset)));
+            columns.push(("amount", SqlValue::Numeric(*amount)));
+        }
+        _ => {}
+    }
+    EventRow { table: OBJECTS_TABLE, columns }
+}
+
+/// Insert statement replacing stored object only by newer version, all columns are overwritten
+/// so columns missing in the row are cleared.
+pub fn object_upsert_statement(row: &EventRow, placeholder: impl Fn(usize, &SqlValue) -> String) -> String {
+    let names: Vec<&str> = row.columns.iter().map(|(name, _)| *name).collect();
+    let values: Vec<String> = row.columns.iter().enumerate().map(|(i, (_, value))| placeholder(i + 1, value)).collect();
+    let updates: Vec<String> = ["version", "type", "owner", "checkpoint", "digest", "deleted", "data", "decoded", "error", "obligation", "asset", "amount"].iter()
+        .map(|name| match names.contains(name) {
+            true => format!("{} = excluded.{}", name, name),
+            false => format!("{} = NULL", name),
+        })
+        .collect();
+    return format!(
+        "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT (object_id) DO UPDATE SET {} WHERE {}.version < excluded.version",
+        row.table, names.join(", "), values.join(", "), updates.join(", "), row.table,
+    );
+}
+
+/// Table with last checkpoint applied to each tracked obligation.
+pub const OBLIGATIONS_TABLE: &str = "obligations";
+/// Table with amounts of obligation per side (`collateral` / `debt`) and asset.
+pub const POSITIONS_TABLE: &str = "obligation_positions";
+
+/// Rows of `obligation_positions` with current amounts of obligation, previous rows of obligation have to be deleted.
+pub fn position_rows(state: &ObligationState) -> Vec<EventRow> {
+    let sides = [("collateral", &state.collaterals), ("debt", &state.debts)];
+    return sides.iter()
+        .flat_map(|(side, amounts)| amounts.iter().map(move |(asset, amount)| EventRow {
+            table: POSITIONS_TABLE,
+            columns: vec![
+                ("obligation", text(&state.id)),
+                ("side", text(side)),
+                ("asset", text(asset)),
+                ("amount", SqlValue::Numeric(*amount)),
+            ],
+        }))
+        .collect();
+}
+
+/// Stored position (obligation, checkpoint, side, asset, amount), side and asset are none for obligation without amounts.
+pub type PositionRow = (String, u64, Option<(String, String, u64)>);
+
+/// States of obligations from rows of `obligations` joined with `obligation_positions`.
+pub fn fold_positions(rows: Vec<PositionRow>) -> Vec<ObligationState> {
+    let mut states: BTreeMap<String, ObligationState> = BTreeMap::new();
+    for (id, checkpoint, position) in rows {
+        let state = states.entry(id.clone()).or_insert_with(|| ObligationState::new(&id));
+        state.checkpoint = checkpoint;
+        match position {
+            Some((side, asset, amount)) if side == "collateral" => { state.collaterals.insert(asset, amount); }
+            Some((_, asset, amount)) => { state.debts.insert(asset, amount); }
+            None => {}
+        }
+    }
+    return states.into_values().collect();
+}
+
+/// Table with oracle price updates, one row per asset and checkpoint.
+pub const PRICES_TABLE: &str = "prices";
+
+pub fn price_row(point: &PricePoint) -> EventRow {
+    EventRow {
+        table: PRICES_TABLE,
+        columns: vec![
+            ("asset", text(&point.asset)),
+            ("checkpoint", SqlValue::BigInt(point.checkpoint as i64)),
+            ("timestamp_ms", SqlValue::BigInt(point.timestamp_ms as i64)),
+            ("value", SqlValue::Numeric(point.value)),
+            ("last_updated", SqlValue::Numeric(point.last_updated)),
+        ],
+    }
+}
+
+/// Table with market configuration of each asset since checkpoint which changed it.
+pub const MARKET_CONFIG_TABLE: &str = "market_config";
+
+/// Row of `market_config`, whole configuration is in `config`, parameters used by health computation
+/// are also in columns (fixed point values raw), columns of models which were not added yet are left out.
+pub fn market_config_row(config: &MarketConfig) -> EventRow {
+    let mut columns = vec![
+        ("asset", text(&config.asset)),
+        ("checkpoint", SqlValue::BigInt(config.checkpoint as i64)),
+        ("config", SqlValue::Json(serde_json::to_string(config).unwrap_or("null".to_string()))),
+    ];
+    if let Some(model) = &config.risk_model {
+        columns.push(("collateral_factor", SqlValue::Numeric(model.collateral_factor.value)));
+        columns.push(("liquidation_factor", SqlValue::Numeric(model.liquidation_factor.value)));
+    }
+    if let Some(model) = &config.interest_model {
+        columns.push(("borrow_weight", SqlValue::Numeric(model.borrow_weight.value)));
+    }
+    if let Some(limit) = config.borrow_limit {
+        columns.push(("borrow_limit", SqlValue::Numeric(limit)));
+    }
+    if let Some(limit) = config.supply_limit {
+        columns.push(("supply_limit", SqlValue::Numeric(limit)));
+    }
+    EventRow { table: MARKET_CONFIG_TABLE, columns }
+}
+
+/// Columns of interest model, fixed point values are stored raw (value / 2^32).
+fn interest_model_columns(model: &InterestModel) -> Vec<(&'static str, SqlValue)> {
+    vec![
+        ("asset", text(&model.type_.name)),
+        ("base_borrow_rate_per_sec", SqlValue::Numeric(model.base_borrow_rate_per_sec.value)),
+        ("interest_rate_scale", SqlValue::Numeric(model.interest_rate_scale)),
+        ("borrow_rate_on_mid_kink", SqlValue::Numeric(model.borrow_rate_on_mid_kink.value)),
+        ("mid_kink", SqlValue::Numeric(model.mid_kink.value)),
+        ("borrow_rate_on_high_kink", SqlValue::Numeric(model.borrow_rate_on_high_kink.value)),
+        ("high_kink", SqlValue::Numeric(model.high_kink.value)),
+        ("max_borrow_rate", SqlValue::Numeric(model.max_borrow_rate.value)),
+        ("revenue_factor", SqlValue::Numeric(model.revenue_factor.value)),
+        ("borrow_weight", SqlValue::Numeric(model.borrow_weight.value)),
+        ("min_borrow_amount", SqlValue::Numeric(model.min_borrow_amount)),
+    ]
+}
+
+/// Columns of risk model, fixed point values are stored raw (value / 2^32).
+fn risk_model_columns(model: &RiskModel) -> Vec<(&'static str, SqlValue)> {
+    vec![
+        ("asset", text(&model.type_.name)),
+        ("collateral_factor", SqlValue::Numeric(model.collateral_factor.value)),
+        ("liquidation_factor", SqlValue::Numeric(model.liquidation_factor.value)),
+        ("liquidation_penalty", SqlValue::Numeric(model.liquidation_penalty.value)),
+        ("liquidation_discount", SqlValue::Numeric(model.liquidation_discount.value)),
+        ("liquidation_revenue_factor", SqlValue::Numeric(model.liquidation_revenue_factor.value)),
+        ("max_collateral_amount", SqlValue::Numeric(model.max_collateral_amount)),
+    ]
+}
+
+/// Epochs of proposed change, it can be applied once `current_epoch + delay_epoches` is reached.
+fn change_columns(current_epoch: u64, delay_epoches: u64, effective_epoches: u64) -> Vec<(&'static str, SqlValue)> {
+    vec![
+        ("current_epoch", SqlValue::Numeric(current_epoch)),
+        ("delay_epoches", SqlValue::Numeric(delay_epoches)),
+        ("effective_epoches", SqlValue::Numeric(effective_epoches)),
+    ]
+}
+
+/// Typed columns of Scallop event, addresses and ids are 0x prefixed hex, `TypeName` is its name.
+pub fn scallop_row(event: &ScallopEvent) -> EventRow {
+    let (table, columns) = match event {
+        ScallopEvent::BorrowEvent(event) => ("borrow_events", vec![
+            ("borrower", text(event.borrower)),
+            ("obligation", text(event.obligation.bytes)),
+            ("asset", text(&event.asset.name)),
+            ("amount", SqlValue::Numeric(event.amount)),
+            ("time", SqlValue::Numeric(event.time)),
+        ]),
+        ScallopEvent::BorrowEventV2(event) => ("borrow_events_v2", vec![
+            ("borrower", text(event.borrower)),
+            ("obligation", text(event.obligation.bytes)),
+            ("asset", text(&event.asset.name)),
+            ("amount", SqlValue::Numeric(event.amount)),
+            ("borrow_fee", SqlValue::Numeric(event.borrow_fee)),
+            ("time", SqlValue::Numeric(event.time)),
+        ]),
+        ScallopEvent::BorrowFlashLoanEvent(event) => ("borrow_flash_loan_events", vec![
+            ("borrower", text(event.borrower)),
+            ("asset", text(&event.asset.name)),
+            ("amount", SqlValue::Numeric(event.amount)),
+        ]),
+        ScallopEvent::CollateralDepositEvent(event) => ("collateral_deposit_events", vec![
+            ("provider", text(event.provider)),
+            ("obligation", text(event.obligation.bytes)),
+            ("deposit_asset", text(&event.deposit_asset.name)),
+            ("deposit_amount", SqlValue::Numeric(event.deposit_amount)),
+        ]),
+        ScallopEvent::CollateralWithdrawEvent(event) => ("collateral_withdraw_events", vec![
+            ("taker", text(event.taker)),
+            ("obligation", text(event.obligation.bytes)),
+            ("withdraw_asset", text(&event.withdraw_asset.name)),
+            ("withdraw_amount", SqlValue::Numeric(event.withdraw_amount)),
+        ]),
+        ScallopEvent::LiquidateEvent(event) => ("liquidate_events", vec![
+            ("liquidator", text(event.liquidator)),
+            ("obligation", text(event.obligation.bytes)),
+            ("debt_type", text(&event.debt_type.name)),
+            ("collateral_type", text(&event.collateral_type.name)),
+            ("repay_on_behalf", SqlValue::Numeric(event.repay_on_behalf)),
+            ("repay_revenue", SqlValue::Numeric(event.repay_revenue)),
+            ("liq_amount", SqlValue::Numeric(event.liq_amount)),
+        ]),
+        ScallopEvent::MintEvent(event) => ("mint_events", vec![
+            ("minter", text(event.minter)),
+            ("deposit_asset", text(&event.deposit_asset.name)),
+            ("deposit_amount", SqlValue::Numeric(event.deposit_amount)),
+            ("mint_asset", text(&event.mint_asset.name)),
+            ("mint_amount", SqlValue::Numeric(event.mint_amount)),
+            ("time", SqlValue::Numeric(event.time)),
+        ]),
+        ScallopEvent::ObligationCreatedEvent(event) => ("obligation_created_events", vec![
+            ("sender", text(event.sender)),
+            ("obligation", text(event.obligation.bytes)),
+            ("obligation_key", text(event.obligation_key.bytes)),
+        ]),
+        ScallopEvent::ObligationLocked(event) => ("obligation_locked_events", vec![
+            ("obligation", text(event.obligation.bytes)),
+            ("witness", text(&event.witness.name)),
+            ("borrow_locked", SqlValue::Bool(event.borrow_locked)),
+            ("repay_locked", SqlValue::Bool(event.repay_locked)),
+            ("deposit_collateral_locked", SqlValue::Bool(event.deposit_collateral_locked)),
+            ("withdraw_collateral_locked", SqlValue::Bool(event.withdraw_collateral_locked)),
+            ("liquidate_locked", SqlValue::Bool(event.liquidate_locked)),
+        ]),
+        ScallopEvent::ObligationUnlocked(event) => ("obligation_unlocked_events", vec![
+            ("obligation", text(event.obligation.bytes)),
+            ("witness", text(&event.witness.name)),
+        ]),
+        ScallopEvent::RedeemEvent(event) => ("redeem_events", vec![
+            ("redeemer", text(event.redeemer)),
+            ("withdraw_asset", text(&event.withdraw_asset.name)),
+            ("withdraw_amount", SqlValue::Numeric(event.withdraw_amount)),
+            ("burn_asset", text(&event.burn_asset.name)),
+            ("burn_amount", SqlValue::Numeric(event.burn_amount)),
+            ("time", SqlValue::Numeric(event.time)),
+        ]),
+        ScallopEvent::RepayEvent(event) => ("repay_events", vec![
+            ("repayer", text(event.repayer)),
+            ("obligation", text(event.obligation.bytes)),
+            ("asset", text(&event.asset.name)),
+            ("amount", SqlValue::Numeric(event.amount)),
+            ("time", SqlValue::Numeric(event.time)),
+        ]),
+        ScallopEvent::RepayFlashLoanEvent(event) => ("repay_flash_loan_events", vec![
+            ("borrower", text(event.borrower)),
+            ("asset", text(&event.asset.name)),
+            ("amount", SqlValue::Numeric(event.amount)),
+        ]),
+        ScallopEvent::InterestModelAdded(event) => {
+            let mut columns = interest_model_columns(&event.interest_model);
+            columns.push(("current_epoch", SqlValue::Numeric(event.current_epoch)));
+            ("interest_model_added_events", columns)
+        }
+        ScallopEvent::InterestModelChangeCreated(event) => {
+            let mut columns = interest_model_columns(&event.interest_model);
+            columns.extend(change_columns(event.current_epoch, event.delay_epoches, event.effective_epoches));
+            ("interest_model_change_created_events", columns)
+        }
+        ScallopEvent::RiskModelAdded(event) => {
+            let mut columns = risk_model_columns(&event.risk_model);
+            columns.push(("current_epoch", SqlValue::Numeric(event.current_epoch)));
+            ("risk_model_added_events", columns)
+        }
+        ScallopEvent::RiskModelChangeCreated(event) => {
+            let mut columns = risk_model_columns(&event.risk_model);
+            columns.extend(change_columns(event.current_epoch, event.delay_epoches, event.effective_epoches));
+            ("risk_model_change_created_events", columns)
+        }
+        ScallopEvent::BorrowLimitChangedEvent(event) => ("borrow_limit_changed_events", vec![
+            ("asset", text(&event.asset.name)),
+            ("borrow_limit", SqlValue::Numeric(event.limit)),
+        ]),
+        ScallopEvent::SupplyLimitChangedEvent(event) => ("supply_limit_changed_events", vec![
+            ("asset", text(&event.asset.name)),
+            ("supply_limit", SqlValue::Numeric(event.limit)),
+        ]),
+        ScallopEvent::InterestAccruedEvent(event) => ("interest_accrued_events", vec![
+            ("asset", text(&event.asset.name)),
+            ("borrow_index", SqlValue::Numeric(event.borrow_index)),
+            ("interest_rate", SqlValue::Numeric(event.interest_rate.value)),
+            ("interest_rate_scale", SqlValue::Numeric(event.interest_rate_scale)),
+            ("last_updated", SqlValue::Numeric(event.last_updated)),
+        ]),
+    };
+    EventRow { table, columns }
+}
@@ -0,0 +1,210 @@
+use std::sync::{Arc, Mutex};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use crate::market::MarketConfig;
+use crate::obligation::ObligationState;
+use crate::prices::PricePoint;
+use crate::sink::relational::{event_rows, fold_positions, insert_statement, market_config_row, object_row, object_upsert_statement, position_rows, price_row, EventRow, SqlValue};
+use crate::sink::{CheckpointBatch, Sink, Watermark};
+
+/// Schema migrations (version, sql), applied version is stored in `PRAGMA user_version`.
+pub const MIGRATIONS: [(i32, &str); 7] = [
+    (1, include_str!("../../migrations/sqlite/0001_init.sql")),
+    (2, include_str!("../../migrations/sqlite/0002_event_sender.sql")),
+    (3, include_str!("../../migrations/sqlite/0003_obligation_positions.sql")),
+    (4, include_str!("../../migrations/sqlite/0004_objects.sql")),
+    (5, include_str!("../../migrations/sqlite/0005_prices.sql")),
+    (6, include_str!("../../migrations/sqlite/0006_market_config.sql")),
+    (7, include_str!("../../migrations/sqlite/0007_market_config_history.sql")),
+];
+
+/// Stores events into sqlite file with the same schema as `PostgresSink` (u64 amounts are decimal TEXT),
+/// each write is one transaction together with watermark. Meant for single node deployments, local inspection and tests.
+pub struct SqliteSink {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteSink {
+    pub fn open(path: &str) -> Result<Self> {
+        info!("opening sqlite database {}", path);
+        let connection = Connection::open(path)?;
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        return Self::new(connection);
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        return Self::new(Connection::open_in_memory()?);
+    }
+
+    fn new(mut connection: Connection) -> Result<Self> {
+        Self::migrate(&mut connection)?;
+        Ok(SqliteSink { connection: Arc::new(Mutex::new(connection)) })
+    }
+
+    fn migrate(connection: &mut Connection) -> Result<()> {
+        let trx = connection.transaction()?;
+        let current: i32 = trx.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        for (version, sql) in MIGRATIONS.iter().filter(|(version, _)| *version > current) {
+            info!("applying sqlite migration {}", version);
+            trx.execute_batch(sql)?;
+            trx.pragma_update(None, "user_version", version)?;
+        }
+        trx.commit()?;
+        Ok(())
+    }
+
+    fn placeholder(n: usize, _value: &SqlValue) -> String {
+        return format!("?{}", n);
+    }
+
+    fn param(value: SqlValue) -> Value {
+        match value {
+            SqlValue::Text(value) | SqlValue::Json(value) => Value::Text(value),
+            // INTEGER column would store u64 above i64::MAX as REAL, amounts are exact decimal TEXT
+            SqlValue::Numeric(value) => Value::Text(value.to_string()),
+            SqlValue::BigInt(value) => Value::Integer(value),
+            SqlValue::Bool(value) => Value::Integer(value as i64),
+            SqlValue::Bytes(value) => Value::Blob(value),
+        }
+    }
+
+    /// Direct access to database, eg. for queries in tests.
+    pub fn with_connection<T>(&self, f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+        let connection = self.connection.lock().map_err(|_| anyhow!("sqlite connection poisoned"))?;
+        return f(&connection);
+    }
+}
+
+#[async_trait]
+impl Sink for SqliteSink {
+    async fn write(&self, batch: &[CheckpointBatch], watermark: &Watermark) -> Result<()> {
+        let mut rows: Vec<EventRow> = vec![];
+        for checkpoint in batch.iter() {
+            for event in checkpoint.events.iter() {
+                if let Err(err) = &event.decoded {
+                    warn!("failed to decode {}: {}", event.key(), err);
+                }
+                rows.extend(event_rows(checkpoint, event));
+            }
+        }
+        let obligations: Vec<ObligationState> = batch.iter().flat_map(|checkpoint| checkpoint.obligations.iter().cloned()).collect();
+        let objects: Vec<EventRow> = batch.iter().flat_map(|checkpoint| checkpoint.objects.iter().map(object_row)).collect();
+        rows.extend(batch.iter().flat_map(|checkpoint| checkpoint.price_updates.iter().map(price_row)));
+        rows.extend(batch.iter().flat_map(|checkpoint| checkpoint.market_configs.iter().map(market_config_row)));
+        let connection = self.connection.clone();
+        let watermark = watermark.clone();
+        // sqlite calls block, so transaction runs outside of async runtime
+        tokio::task::spawn_blocking(move || {
+            let mut connection = connection.lock().map_err(|_| anyhow!("sqlite connection poisoned"))?;
+            let trx = connection.transaction()?;
+            debug!("writing {} rows for checkpoint {}", rows.len(), watermark.checkpoint);
+            for row in rows {
+                let mut statement = trx.prepare_cached(&insert_statement(&row, Self::placeholder))?;
+                statement.execute(params_from_iter(row.columns.into_iter().map(|(_, value)| Self::param(value))))?;
+            }
+            for state in obligations.iter() {
+                trx.execute(
+                    "INSERT INTO obligations (obligation, checkpoint) VALUES (?1, ?2) ON CONFLICT (obligation) DO UPDATE SET checkpoint = excluded.checkpoint",
+                    params![state.id, state.checkpoint as i64],
+                )?;
+                trx.execute("DELETE FROM obligation_positions WHERE obligation = ?1", params![state.id])?;
+                for row in position_rows(state) {
+                    let mut statement = trx.prepare_cached(&insert_statement(&row, Self::placeholder))?;
+                    statement.execute(params_from_iter(row.columns.into_iter().map(|(_, value)| Self::param(value))))?;
+                }
+            }
+            for row in objects {
+                let mut statement = trx.prepare_cached(&object_upsert_statement(&row, Self::placeholder))?;
+                statement.execute(params_from_iter(row.columns.into_iter().map(|(_, value)| Self::param(value))))?;
+            }
+            trx.execute(
+                "INSERT INTO watermarks (name, checkpoint) VALUES (?1, ?2) ON CONFLICT (name) DO UPDATE SET checkpoint = excluded.checkpoint",
+                params![watermark.name, watermark.checkpoint as i64],
+            )?;
+            trx.commit()?;
+            Ok::<_, anyhow::Error>(())
+        }).await?
+    }
+
+    async fn watermark(&self, name: &str) -> Result<Option<u64>> {
+        return self.with_connection(|connection| {
+            let checkpoint = connection
+                .query_row("SELECT checkpoint FROM watermarks WHERE name = ?1", params![name], |row| row.get::<_, i64>(0))
+                .optional()?;
+            Ok(checkpoint.map(|checkpoint| checkpoint as u64))
+        });
+    }
+
+    async fn obligations(&self, ids: &[String]) -> Result<Vec<ObligationState>> {
+        return self.with_connection(|connection| {
+            let mut statement = connection.prepare_cached(
+                "SELECT o.obligation, o.checkpoint, p.side, p.asset, p.amount FROM obligations o \
+                 LEFT JOIN obligation_positions p ON p.obligation = o.obligation WHERE o.obligation = ?1",
+            )?;
+            let mut positions = vec![];
+            for id in ids.iter() {
+                let rows = statement.query_map(params![id], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, Option<String>>(2)?, row.get::<_, Option<String>>(3)?, row.get::<_, Option<String>>(4)?))
+                })?;
+                for row in rows {
+                    let (id, checkpoint, side, asset, amount) = row?;
+                    let position = match (side, asset, amount) {
+                        (Some(side), Some(asset), Some(amount)) => Some((side, asset, parse_amount(&amount)?)),
+                        _ => None,
+                    };
+                    positions.push((id, checkpoint as u64, position));
+                }
+            }
+            Ok(fold_positions(positions))
+        });
+    }
+
+    async fn latest_prices(&self, assets: &[String], checkpoint: u64) -> Result<Vec<PricePoint>> {
+        return self.with_connection(|connection| {
+            let mut statement = connection.prepare_cached(
+                "SELECT checkpoint, timestamp_ms, value, last_updated FROM prices \
+                 WHERE asset = ?1 AND checkpoint <= ?2 ORDER BY checkpoint DESC LIMIT 1",
+            )?;
+            let mut points = vec![];
+            for asset in assets.iter() {
+                let row = statement.query_row(params![asset, checkpoint as i64], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?))
+                }).optional()?;
+                if let Some((checkpoint, timestamp_ms, value, last_updated)) = row {
+                    points.push(PricePoint {
+                        asset: asset.clone(),
+                        checkpoint: checkpoint as u64,
+                        timestamp_ms: timestamp_ms as u64,
+                        value: parse_amount(&value)?,
+                        last_updated: parse_amount(&last_updated)?,
+                    });
+                }
+            }
+            Ok(points)
+        });
+    }
+
+    async fn market_configs(&self, assets: &[String], checkpoint: u64) -> Result<Vec<MarketConfig>> {
+        return self.with_connection(|connection| {
+            let mut statement = connection.prepare_cached(
+                "SELECT config FROM market_config WHERE asset = ?1 AND checkpoint <= ?2 ORDER BY checkpoint DESC LIMIT 1",
+            )?;
+            let mut configs = vec![];
+            for asset in assets.iter() {
+                let config = statement.query_row(params![asset, checkpoint as i64], |row| row.get::<_, String>(0)).optional()?;
+                if let Some(config) = config {
+                    configs.push(serde_json::from_str(&config)?);
+                }
+            }
+            Ok(configs)
+        });
+    }
+}
+
+/// Amounts are stored as decimal TEXT.
+fn parse_amount(amount: &str) -> Result<u64> {
+    return amount.parse::<u64>().map_err(|err| anyhow!("invalid amount {}: {}", amount, err));
+}
@@ -0,0 +1,73 @@
+#![allow(dead_code)]
+use std::collections::BTreeMap;
+use sui_indexer::decoder::DecoderRegistry;
+use sui_indexer::events::IndexerData;
+use sui_indexer::sink::{CheckpointBatch, IndexedEvent};
+
+pub const MINT_EVENT_TYPE: &str = "0xefe8b36d5b2e43728cc323298626b83177803521d195cfb11e15b910e892fddf::mint::MintEvent";
+
+pub fn mint_event_bytes() -> Vec<u8> {
+    let b :[u8; 294] = [
+        91, 209,  16,  66,  62,   6, 174, 246,  21,  36, 137,  58,
+        75, 171, 159, 103, 195, 157, 239, 112,  27, 203,  53,   4,
+        160, 137,  13,  10,  56, 211, 236,  97,  74,  48,  48,  48,
+        48,  48,  48,  48,  48,  48,  48,  48,  48,  48,  48,  48,
+        48,  48,  48,  48,  48,  48,  48,  48,  48,  48,  48,  48,
+        48,  48,  48,  48,  48,  48,  48,  48,  48,  48,  48,  48,
+        48,  48,  48,  48,  48,  48,  48,  48,  48,  48,  48,  48,
+        48,  48,  48,  48,  48,  48,  48,  48,  48,  48,  48,  48,
+        50,  58,  58, 115, 117, 105,  58,  58,  83,  85,  73,   0,
+        132, 184,  13,   6,   0,   0,   0, 161,   1, 101, 102, 101,
+        56,  98,  51,  54, 100,  53,  98,  50, 101,  52,  51,  55,
+        50,  56,  99,  99,  51,  50,  51,  50,  57,  56,  54,  50,
+        54,  98,  56,  51,  49,  55,  55,  56,  48,  51,  53,  50,
+        49, 100,  49,  57,  53,  99, 102,  98,  49,  49, 101,  49,
+        53,  98,  57,  49,  48, 101,  56,  57,  50, 102, 100, 100,
+        102,  58,  58, 114, 101, 115, 101, 114, 118, 101,  58,  58,
+        77,  97, 114, 107, 101, 116,  67, 111, 105, 110,  60,  48,
+        48,  48,  48,  48,  48,  48,  48,  48,  48,  48,  48,  48,
+        48,  48,  48,  48,  48,  48,  48,  48,  48,  48,  48,  48,
+        48,  48,  48,  48,  48,  48,  48,  48,  48,  48,  48,  48,
+        48,  48,  48,  48,  48,  48,  48,  48,  48,  48,  48,  48,
+        48,  48,  48,  48,  48,  48,  48,  48,  48,  48,  48,  48,
+        48,  48,  50,  58,  58, 115, 117, 105,  58,  58,  83,  85,
+        73,  62, 145,  53,   5,   2,   6,   0,   0,   0,  89,  62,
+        225, 101,   0,   0,   0,   0
+    ];
+    return b.to_vec();
+}
+
+/// Checkpoint with one mint event and one event which can not be decoded.
+pub fn mint_batch(checkpoint: u64) -> CheckpointBatch {
+    let registry = DecoderRegistry::scallop();
+    let data = [
+        (mint_event_bytes(), MINT_EVENT_TYPE.to_string()),
+        (vec![1, 2, 3], MINT_EVENT_TYPE.replace("MintEvent", "UnknownEvent")),
+    ];
+    let events = data.into_iter().enumerate().map(|(index, (bytes, type_))| {
+        let data = IndexerData {
+            digest: "6ZqFMeUdbA7P1JxUsXnwJ5NgqrZgBsmMCbB2qzqMkhtd".to_string(),
+            checkpoint,
+            epoch: 120,
+            data: bytes,
+            index: index as u64,
+            type_,
+            sender: "0x5bd110423e06aef61524893a4bab9f67c39def701bcb3504a0890d0a38d3ec61".to_string(),
+        };
+        let decoded = registry.decode(&data.data, &data.type_);
+        IndexedEvent { data, decoded }
+    }).collect();
+    CheckpointBatch { checkpoint, epoch: 120, timestamp_ms: 1709390000000, events, obligations: vec![], objects: vec![], price_updates: vec![], prices: BTreeMap::new(), market_configs: vec![] }
+}
+
+/// Redis of redis tests set by `REDIS_URL`, eg. `docker run -p 6379:6379 redis:7` and `REDIS_URL=redis://localhost:6379`.
+/// Without `REDIS_URL` the test is skipped.
+pub fn redis_url() -> Option<String> {
+    match std::env::var("REDIS_URL") {
+        Ok(url) => Some(url),
+        Err(_) => {
+            println!("REDIS_URL is not set, skipping redis test");
+            None
+        }
+    }
+}
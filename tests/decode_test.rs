@@ -0,0 +1,71 @@
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use sui_indexer::decoder::{DecoderRegistry, EventType, JsonDecoder};
+use sui_indexer::events::{parse, DecodeError, EventPayload, ScallopEvent, SCALLOP_PACKAGE_V3};
+use common::{mint_event_bytes, MINT_EVENT_TYPE};
+
+mod common;
+
+#[test]
+fn decode_test(){
+    let decoded = parse(&mint_event_bytes(), MINT_EVENT_TYPE).unwrap();
+    assert_eq!(decoded.name, "MintEvent");
+    assert!(decoded.obligation.is_none());
+    assert!(matches!(decoded.event, EventPayload::Scallop(ScallopEvent::MintEvent(_))));
+}
+
+#[test]
+fn decode_errors_test(){
+    let mut bytes = mint_event_bytes();
+    bytes.push(0);
+    assert!(matches!(parse(&bytes, MINT_EVENT_TYPE), Err(DecodeError::TrailingBytes { .. })));
+    let bytes = mint_event_bytes();
+    assert!(matches!(parse(&bytes[..100], MINT_EVENT_TYPE), Err(DecodeError::Layout { .. })));
+    assert!(matches!(parse(&bytes, "0x2::coin::CoinMetadata"), Err(DecodeError::UnknownType(_))));
+}
+
+#[test]
+fn decode_dispatch_test(){
+    // same struct name in another package is not a Scallop event
+    let other = "0x0000000000000000000000000000000000000000000000000000000000000abc::mint::MintEvent";
+    assert!(matches!(parse(&mint_event_bytes(), other), Err(DecodeError::UnknownType(_))));
+    // nor is same struct name in another module of Scallop package
+    let other_module = MINT_EVENT_TYPE.replace("::mint::", "::redeem::");
+    assert!(matches!(parse(&mint_event_bytes(), &other_module), Err(DecodeError::UnknownType(_))));
+}
+
+#[test]
+fn decode_upgrade_test(){
+    // struct introduced by V3 has no decoder, event is kept as dead letter instead of being misdecoded
+    let v3 = format!("{}::borrow::BorrowEventV3", SCALLOP_PACKAGE_V3);
+    assert!(matches!(parse(&mint_event_bytes(), &v3), Err(DecodeError::UnknownType(_))));
+    // struct of original package has its address even when emitted by V3 function
+    assert!(parse(&mint_event_bytes(), MINT_EVENT_TYPE).is_ok());
+}
+
+// layout of MintEvent, as if it was event of other protocol
+#[derive(Serialize,Deserialize)]
+struct Minted {
+    minter: [u8; 32],
+    deposit_asset: String,
+    deposit_amount: u64,
+    mint_asset: String,
+    mint_amount: u64,
+    time: u64,
+}
+
+#[test]
+fn decode_registry_test(){
+    let other = "0x0000000000000000000000000000000000000000000000000000000000000abc::mint::Minted";
+    let mut registry = DecoderRegistry::scallop();
+    registry.register(EventType::parse(other).unwrap(), Arc::new(JsonDecoder::<Minted>::new(|event| Some(event.mint_asset.clone()))));
+    let decoded = registry.decode(&mint_event_bytes(), other).unwrap();
+    assert_eq!(decoded.name, "Minted");
+    assert!(decoded.scallop().is_none());
+    match decoded.event {
+        EventPayload::Json(value) => assert_eq!(value["deposit_amount"], 26000000000u64),
+        EventPayload::Scallop(_) => panic!("expected json payload"),
+    }
+    // scallop decoders are still registered
+    assert!(registry.decode(&mint_event_bytes(), MINT_EVENT_TYPE).unwrap().scallop().is_some());
+}
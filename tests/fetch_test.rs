@@ -18,6 +18,7 @@ use reqwest::Result;
 use tokio::sync::mpsc;
 use tokio::sync::mpsc::Sender;
 use tokio::time::sleep;
+use sui_indexer::filter::PackageFilter;
 use sui_indexer::reader::CheckpointReader;
 
 // type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
@@ -66,21 +67,19 @@ async fn fetch_checkpoint(){
     }
     println!("writing to files took: {} ms", start.elapsed().unwrap().as_millis());
     let checkpoint_data = Blob::from_bytes::<CheckpointData>(&response1.bytes().await.unwrap()).unwrap();
-    let filter = vec![ObjectID::from_str("0xefe8b36d5b2e43728cc323298626b83177803521d195cfb11e15b910e892fddf").unwrap(),
+    let filter = PackageFilter::from_packages(vec![ObjectID::from_str("0xefe8b36d5b2e43728cc323298626b83177803521d195cfb11e15b910e892fddf").unwrap(),
                       ObjectID::from_str("0xc38f849e81cfe46d4e4320f508ea7dda42934a329d5a6571bb4c3cb6ea63f5da").unwrap(),
-    ];
+    ]);
     let result = process_txn(&checkpoint_data, &filter);
     println!("{:?}", result);
     for (digest, indexer_data) in result {
         let start = SystemTime::now();
         let event = indexer_data.parse_event();
         let mut digest_modified = format!("{}::{}::{}",  indexer_data.checkpoint, digest, indexer_data.index);
-        if event.is_some() {
-            let (_, event_name, obligation_id) = event.unwrap();
+        if let Ok(event) = event {
             digest_modified.push_str("::");
-            digest_modified.push_str(&event_name);
-            if obligation_id.is_some() {
-                let id = obligation_id.unwrap();
+            digest_modified.push_str(&event.name);
+            if let Some(id) = event.obligation {
                 digest_modified.push_str("::");
                 digest_modified.push_str(&id);
             }
@@ -0,0 +1,20 @@
+use std::time::SystemTime;
+use futures::StreamExt;
+use sui_indexer::fetcher::{ArchiveFetcher, MAINNET_ARCHIVE_URL};
+
+#[tokio::test]
+async fn fetcher_test(){
+    let start = SystemTime::now();
+    let mut fetcher = ArchiveFetcher::new(MAINNET_ARCHIVE_URL);
+    fetcher.concurrency = 10;
+    let results: Vec<_> = fetcher.stream(27668000..27668050).collect().await;
+    assert_eq!(results.len(), 50);
+    for (expected, result) in (27668000..27668050).zip(results) {
+        let (number, checkpoint) = result.unwrap();
+        assert_eq!(number, expected);
+        assert_eq!(checkpoint.unwrap().checkpoint_summary.sequence_number, expected);
+    }
+    println!("took: {}", start.elapsed().unwrap().as_millis());
+    // far in the future, not in archive
+    assert!(fetcher.fetch(u64::MAX / 2).await.unwrap().is_none());
+}
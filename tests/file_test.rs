@@ -0,0 +1,67 @@
+use std::fs;
+use sui_indexer::sink::file::{FileSink, OutputFormat};
+use sui_indexer::sink::{Sink, Watermark, LIVE_WATERMARK};
+use common::mint_batch;
+
+mod common;
+
+fn output_dir(name: &str) -> String {
+    let dir = std::env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&dir);
+    return dir.to_str().unwrap().to_string();
+}
+
+#[tokio::test]
+async fn jsonl_sink_test(){
+    let dir = output_dir("indexer_jsonl_test");
+    let sink = FileSink::open(OutputFormat::Jsonl, &dir, 500).unwrap();
+    let watermark = Watermark { name: LIVE_WATERMARK.to_string(), checkpoint: 10964321 };
+    sink.write(&[mint_batch(10964321)], &watermark).await.unwrap();
+    assert_eq!(sink.watermark(LIVE_WATERMARK).await.unwrap(), Some(10964321));
+    let content = fs::read_to_string(format!("{}/events-10964321.jsonl", dir)).unwrap();
+    let lines: Vec<serde_json::Value> = content.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["name"], "MintEvent");
+    assert_eq!(lines[0]["event"]["MintEvent"]["deposit_amount"], 26000000000u64);
+    assert!(lines[1]["error"].is_string());
+}
+
+#[tokio::test]
+async fn csv_sink_test(){
+    let dir = output_dir("indexer_csv_test");
+    let sink = FileSink::open(OutputFormat::Csv, &dir, 1_000_000).unwrap();
+    let watermark = Watermark { name: LIVE_WATERMARK.to_string(), checkpoint: 10964321 };
+    sink.write(&[mint_batch(10964321)], &watermark).await.unwrap();
+    let content = fs::read_to_string(format!("{}/mint_events-10964321.csv", dir)).unwrap();
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("checkpoint,digest,event_index,epoch,timestamp_ms,minter"));
+    assert!(lines[1].contains(",26000000000,"));
+}
+
+#[tokio::test]
+async fn file_rotation_test(){
+    // size of file with one checkpoint
+    let measured = output_dir("indexer_rotation_size_test");
+    let sink = FileSink::open(OutputFormat::Csv, &measured, u64::MAX).unwrap();
+    sink.write(&[mint_batch(10964321)], &Watermark { name: LIVE_WATERMARK.to_string(), checkpoint: 10964321 }).await.unwrap();
+    let one_checkpoint = fs::metadata(format!("{}/mint_events-10964321.csv", measured)).unwrap().len();
+    let dir = output_dir("indexer_rotation_test");
+    // file reaches limit with the second checkpoint, it is closed after the whole write
+    let sink = FileSink::open(OutputFormat::Csv, &dir, one_checkpoint + 1).unwrap();
+    for checkpoint in 10964321..10964324 {
+        let watermark = Watermark { name: LIVE_WATERMARK.to_string(), checkpoint };
+        sink.write(&[mint_batch(checkpoint)], &watermark).await.unwrap();
+    }
+    let first = fs::read_to_string(format!("{}/mint_events-10964321.csv", dir)).unwrap();
+    assert_eq!(first.lines().count(), 3);
+    assert!(fs::metadata(format!("{}/mint_events-10964322.csv", dir)).is_err());
+    // new file is named by its first checkpoint and starts with header
+    let second: Vec<String> = fs::read_to_string(format!("{}/mint_events-10964323.csv", dir)).unwrap().lines().map(String::from).collect();
+    assert_eq!(second.len(), 2);
+    assert_eq!(second[0], first.lines().next().unwrap());
+    assert!(second[1].starts_with("10964323,"));
+    // watermark is stored after every write
+    assert_eq!(sink.watermark(LIVE_WATERMARK).await.unwrap(), Some(10964323));
+    assert_eq!(FileSink::open(OutputFormat::Csv, &dir, 1).unwrap().watermark(LIVE_WATERMARK).await.unwrap(), Some(10964323));
+}
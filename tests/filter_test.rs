@@ -0,0 +1,60 @@
+use std::str::FromStr;
+use sui_types::base_types::ObjectID;
+use sui_indexer::events::{SCALLOP_PACKAGE, SCALLOP_PACKAGE_V2, SCALLOP_PACKAGE_V3};
+use sui_indexer::filter::{FilterConfig, PackageFilter};
+
+#[test]
+fn filter_config_test(){
+    let config = FilterConfig::from_toml(&format!(r#"
+        [[packages]]
+        id = "{}"
+        exclude = ["flash_loan", "borrow::BorrowEvent"]
+    "#, SCALLOP_PACKAGE)).unwrap();
+    let filter = PackageFilter::new(&config);
+    let scallop = ObjectID::from_str(SCALLOP_PACKAGE).unwrap();
+    assert!(filter.allowed(&scallop, "mint", "MintEvent"));
+    assert!(filter.allowed(&scallop, "borrow", "BorrowEventV2"));
+    assert!(!filter.allowed(&scallop, "borrow", "BorrowEvent"));
+    assert!(!filter.allowed(&scallop, "flash_loan", "RepayFlashLoanEvent"));
+    assert!(!filter.allowed(&ObjectID::random(), "mint", "MintEvent"));
+}
+
+#[test]
+fn filter_flags_test(){
+    let other = ObjectID::random();
+    let mut config = FilterConfig::scallop();
+    config.add_include(&format!("{}::pool::Swapped", other)).unwrap();
+    config.add_exclude(&format!("{}::redeem", SCALLOP_PACKAGE)).unwrap();
+    let filter = PackageFilter::new(&config);
+    assert_eq!(filter.packages().len(), 4);
+    assert!(filter.allowed(&other, "pool", "Swapped"));
+    assert!(!filter.allowed(&other, "pool", "Deposited"));
+    assert!(!filter.allowed(&ObjectID::from_str(SCALLOP_PACKAGE).unwrap(), "redeem", "RedeemEvent"));
+    assert!(config.add_include("mint::MintEvent").is_err());
+}
+
+#[test]
+fn filter_cli_flags_test(){
+    let scallop = ObjectID::from_str(SCALLOP_PACKAGE).unwrap();
+    let upgrades = [ObjectID::from_str(SCALLOP_PACKAGE_V2).unwrap(), ObjectID::from_str(SCALLOP_PACKAGE_V3).unwrap()];
+    // lone --exclude keeps all Scallop packages
+    let config = FilterConfig::from_flags(None, &[], &[], &[format!("{}::flash_loan", SCALLOP_PACKAGE)]).unwrap();
+    let filter = PackageFilter::new(&config);
+    assert_eq!(filter.packages().len(), 3);
+    assert!(!filter.allowed(&scallop, "flash_loan", "BorrowFlashLoanEvent"));
+    assert!(filter.allowed(&scallop, "mint", "MintEvent"));
+    assert!(upgrades.iter().all(|package| filter.allowed(package, "borrow", "BorrowEventV2")));
+    // lone --include of other package is indexed next to Scallop
+    let other = ObjectID::random();
+    let config = FilterConfig::from_flags(None, &[], &[format!("{}::pool", other)], &[]).unwrap();
+    let filter = PackageFilter::new(&config);
+    assert_eq!(filter.packages().len(), 4);
+    assert!(filter.allowed(&scallop, "mint", "MintEvent"));
+    assert!(filter.allowed(&other, "pool", "Swapped"));
+    assert!(!filter.allowed(&other, "router", "Swapped"));
+    // --package selects packages instead of Scallop
+    let config = FilterConfig::from_flags(None, &[other.to_string()], &[], &[]).unwrap();
+    let filter = PackageFilter::new(&config);
+    assert_eq!(filter.packages().len(), 1);
+    assert!(!filter.allowed(&scallop, "mint", "MintEvent"));
+}
@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+use sui_indexer::health::{HealthCalculator, RiskConfig};
+use sui_indexer::obligation::ObligationState;
+
+const SUI: &str = "0000000000000000000000000000000000000000000000000000000000000002::sui::SUI";
+const USDC: &str = "5d4b302506645c37ff133b98c4b50a5ae14841659738d6d733d59d0d217a93bf::coin::COIN";
+const CETUS: &str = "06864a6f921804860930db6ddbe2e16acdf8504495ea7481637a1c8b9a8fe54b::cetus::CETUS";
+
+const RISK: &str = r#"
+[[assets]]
+asset = "0000000000000000000000000000000000000000000000000000000000000002::sui::SUI"
+decimals = 9
+collateral_factor = 0.6
+liquidation_factor = 0.8
+
+[[assets]]
+asset = "5d4b302506645c37ff133b98c4b50a5ae14841659738d6d733d59d0d217a93bf::coin::COIN"
+decimals = 6
+collateral_factor = 0.8
+liquidation_factor = 0.9
+borrow_weight = 1.25
+
+[prices]
+"0000000000000000000000000000000000000000000000000000000000000002::sui::SUI" = 2.0
+"5d4b302506645c37ff133b98c4b50a5ae14841659738d6d733d59d0d217a93bf::coin::COIN" = 1.0
+"#;
+
+fn obligation(id: &str, sui_collateral: u64, usdc_debt: u64) -> ObligationState {
+    ObligationState {
+        id: id.to_string(),
+        collaterals: BTreeMap::from([(SUI.to_string(), sui_collateral)]),
+        debts: BTreeMap::from([(USDC.to_string(), usdc_debt)]),
+        checkpoint: 100,
+    }
+}
+
+#[test]
+fn health_factor_test(){
+    let calculator = HealthCalculator::new(&RiskConfig::from_toml(RISK).unwrap());
+    // 100 SUI at 2 USD, 100 USDC debt weighted to 125 USD
+    let health = calculator.health(&obligation("0x1", 100_000_000_000, 100_000_000));
+    assert_eq!(health.collateral_value, 200.0);
+    assert_eq!(health.borrow_capacity, 120.0);
+    assert_eq!(health.liquidation_value, 160.0);
+    assert_eq!(health.debt_value, 125.0);
+    assert_eq!(health.health_factor, Some(1.28));
+    assert_eq!(health.available_borrow(), 0.0);
+    assert!(!health.liquidatable());
+    assert!(health.missing.is_empty());
+    // without debt there is no health factor
+    assert_eq!(calculator.health(&obligation("0x2", 1, 0)).health_factor, None);
+}
+
+#[test]
+fn health_below_threshold_test(){
+    let mut calculator = HealthCalculator::new(&RiskConfig::from_toml(RISK).unwrap());
+    let mut unknown = obligation("0x4", 100_000_000_000, 10_000_000);
+    unknown.collaterals.insert(CETUS.to_string(), 1000);
+    let obligations = vec![
+        obligation("0x1", 100_000_000_000, 100_000_000),
+        obligation("0x2", 100_000_000_000, 150_000_000),
+        obligation("0x3", 100_000_000_000, 10_000_000),
+        unknown,
+    ];
+    let risky = calculator.below(obligations.iter(), 1.1);
+    assert_eq!(risky.iter().map(|health| health.obligation.as_str()).collect::<Vec<_>>(), vec!["0x2"]);
+    assert!(risky[0].liquidatable());
+    // price drop makes more obligations risky, order is the riskiest first
+    calculator.set_price(SUI, 1.5);
+    let risky = calculator.below(obligations.iter(), 1.1);
+    assert_eq!(risky.iter().map(|health| health.obligation.as_str()).collect::<Vec<_>>(), vec!["0x2", "0x1"]);
+    assert_eq!(calculator.health(&obligations[3]).missing, vec![CETUS.to_string()]);
+}
@@ -0,0 +1,54 @@
+#![cfg(feature = "kafka")]
+use std::time::Duration;
+use rdkafka::consumer::{BaseConsumer, Consumer};
+use rdkafka::{ClientConfig, Message};
+use sui_indexer::sink::kafka::{KafkaOptions, KafkaSink};
+use sui_indexer::sink::{Sink, Watermark, LIVE_WATERMARK};
+use common::mint_batch;
+
+mod common;
+
+/// Needs local broker, eg. redpanda (see README), topics are prefixed by test run.
+#[tokio::test]
+async fn kafka_abort_test(){
+    let brokers = std::env::var("KAFKA_BROKERS").unwrap_or("localhost:9092".to_string());
+    let run = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_millis();
+    let options = KafkaOptions {
+        brokers: brokers.clone(),
+        topic_prefix: format!("test{}.", run),
+        transactional_id: format!("indexer-test-{}", run),
+    };
+    let sink = KafkaSink::connect(options.clone()).await.unwrap();
+    assert_eq!(sink.watermark(LIVE_WATERMARK).await.unwrap(), None);
+    let batch = mint_batch(10964321);
+    let watermark = Watermark { name: LIVE_WATERMARK.to_string(), checkpoint: 10964321 };
+    sink.write(std::slice::from_ref(&batch), &watermark).await.unwrap();
+    // dead letter above message.max.bytes is rejected, mint event sent before it in the transaction is aborted
+    let mut failing = mint_batch(10964322);
+    failing.events[1].data.data = vec![0; 1_000_000];
+    let watermark = Watermark { name: LIVE_WATERMARK.to_string(), checkpoint: 10964322 };
+    assert!(sink.write(std::slice::from_ref(&failing), &watermark).await.is_err());
+    assert_eq!(sink.watermark(LIVE_WATERMARK).await.unwrap(), Some(10964321));
+    // producer continues with next transaction after abort
+    let retried = mint_batch(10964322);
+    sink.write(std::slice::from_ref(&retried), &watermark).await.unwrap();
+    assert_eq!(sink.watermark(LIVE_WATERMARK).await.unwrap(), Some(10964322));
+
+    let consumer: BaseConsumer = ClientConfig::new()
+        .set("bootstrap.servers", &brokers)
+        .set("group.id", format!("indexer-test-{}", run))
+        .set("auto.offset.reset", "earliest")
+        .set("isolation.level", "read_committed")
+        .create()
+        .unwrap();
+    consumer.subscribe(&[&format!("{}MintEvent", options.topic_prefix)]).unwrap();
+    let mut checkpoints = vec![];
+    // aborted messages and transaction markers are skipped, end of topic is recognized by poll timeout
+    while let Some(message) = consumer.poll(Duration::from_secs(10)) {
+        let message = message.unwrap();
+        let event: serde_json::Value = serde_json::from_slice(message.payload().unwrap()).unwrap();
+        assert_eq!(message.key(), Some(batch.events[0].data.digest.as_bytes()));
+        checkpoints.push(event["checkpoint"].as_u64().unwrap());
+    }
+    assert_eq!(checkpoints, vec![10964321, 10964322]);
+}
@@ -0,0 +1,243 @@
+use std::collections::BTreeMap;
+use sui_indexer::events::{parse, BorrowLimitChangedEvent, FixedPoint32, IndexerData, InterestAccruedEvent, InterestModel, InterestModelAdded, RiskModel, RiskModelAdded, RiskModelChangeCreated, ScallopEvent, SupplyLimitChangedEvent, TypeName, SCALLOP_PACKAGE};
+use sui_indexer::market::MarketHistory;
+use sui_indexer::sink::{CheckpointBatch, IndexedEvent};
+
+const SUI: &str = "0000000000000000000000000000000000000000000000000000000000000002::sui::SUI";
+
+/// Fixed point value of `numerator / 100`.
+fn percent(numerator: u64) -> FixedPoint32 {
+    FixedPoint32 { value: (numerator << 32) / 100 }
+}
+
+fn risk_model(collateral_factor: u64) -> RiskModel {
+    RiskModel {
+        type_: TypeName { name: SUI.to_string() },
+        collateral_factor: percent(collateral_factor),
+        liquidation_factor: percent(collateral_factor + 10),
+        liquidation_penalty: percent(5),
+        liquidation_discount: percent(4),
+        liquidation_revenue_factor: percent(1),
+        max_collateral_amount: 1_000_000_000_000,
+    }
+}
+
+fn interest_model() -> InterestModel {
+    InterestModel {
+        type_: TypeName { name: SUI.to_string() },
+        base_borrow_rate_per_sec: FixedPoint32 { value: 0 },
+        interest_rate_scale: 1000,
+        borrow_rate_on_mid_kink: percent(10),
+        mid_kink: percent(60),
+        borrow_rate_on_high_kink: percent(30),
+        high_kink: percent(90),
+        max_borrow_rate: percent(300),
+        revenue_factor: percent(10),
+        borrow_weight: percent(125),
+        min_borrow_amount: 1000,
+    }
+}
+
+/// Checkpoint with market events decoded from their bcs bytes.
+fn batch(checkpoint: u64, events: Vec<(&str, Vec<u8>)>) -> CheckpointBatch {
+    let events = events.into_iter().enumerate().map(|(index, (type_, bytes))| {
+        let data = IndexerData {
+            digest: format!("digest{}", checkpoint),
+            checkpoint,
+            epoch: 300,
+            data: bytes,
+            index: index as u64,
+            type_: format!("{}::{}", SCALLOP_PACKAGE, type_),
+            sender: "0x0".to_string(),
+        };
+        let decoded = data.parse_event();
+        IndexedEvent { data, decoded }
+    }).collect();
+    CheckpointBatch { checkpoint, epoch: 300, timestamp_ms: 1709390000000, events, obligations: vec![], objects: vec![], price_updates: vec![], prices: BTreeMap::new(), market_configs: vec![] }
+}
+
+fn history() -> Vec<CheckpointBatch> {
+    let listed = vec![
+        ("interest_model::InterestModelAdded", bcs::to_bytes(&InterestModelAdded { interest_model: interest_model(), current_epoch: 100 }).unwrap()),
+        ("risk_model::RiskModelAdded", bcs::to_bytes(&RiskModelAdded { risk_model: risk_model(60), current_epoch: 100 }).unwrap()),
+    ];
+    let proposed = vec![
+        ("risk_model::RiskModelChangeCreated", bcs::to_bytes(&RiskModelChangeCreated { risk_model: risk_model(70), current_epoch: 101, delay_epoches: 7, effective_epoches: 108 }).unwrap()),
+    ];
+    let applied = vec![
+        ("risk_model::RiskModelAdded", bcs::to_bytes(&RiskModelAdded { risk_model: risk_model(70), current_epoch: 108 }).unwrap()),
+    ];
+    let limits = vec![
+        ("app::BorrowLimitChangedEvent", bcs::to_bytes(&BorrowLimitChangedEvent { asset: TypeName { name: SUI.to_string() }, limit: 500_000_000_000 }).unwrap()),
+        ("app::SupplyLimitChangedEvent", bcs::to_bytes(&SupplyLimitChangedEvent { asset: TypeName { name: SUI.to_string() }, limit: 2_000_000_000_000 }).unwrap()),
+        ("market::InterestAccruedEvent", bcs::to_bytes(&accrued()).unwrap()),
+    ];
+    vec![batch(1000, listed), batch(1100, proposed), batch(1800, applied), batch(2000, limits)]
+}
+
+fn accrued() -> InterestAccruedEvent {
+    InterestAccruedEvent {
+        asset: TypeName { name: SUI.to_string() },
+        borrow_index: 1_050_000_000,
+        interest_rate: percent(3),
+        interest_rate_scale: 1000,
+        last_updated: 1709390000,
+    }
+}
+
+#[test]
+fn decode_market_events_test(){
+    let bytes = bcs::to_bytes(&RiskModelAdded { risk_model: risk_model(60), current_epoch: 100 }).unwrap();
+    let decoded = parse(&bytes, &format!("{}::risk_model::RiskModelAdded", SCALLOP_PACKAGE)).unwrap();
+    assert!(decoded.obligation.is_none());
+    match decoded.scallop() {
+        Some(ScallopEvent::RiskModelAdded(event)) => assert_eq!(event.risk_model, risk_model(60)),
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(parse(&bytes[..40], &format!("{}::risk_model::RiskModelAdded", SCALLOP_PACKAGE)).is_err());
+
+    let bytes = bcs::to_bytes(&BorrowLimitChangedEvent { asset: TypeName { name: SUI.to_string() }, limit: 500_000_000_000 }).unwrap();
+    let decoded = parse(&bytes, &format!("{}::app::BorrowLimitChangedEvent", SCALLOP_PACKAGE)).unwrap();
+    let event = decoded.scallop().unwrap();
+    assert!(matches!(event, ScallopEvent::BorrowLimitChangedEvent(event) if event.limit == 500_000_000_000));
+    assert_eq!(event.market_asset(), Some(SUI));
+    let bytes = bcs::to_bytes(&accrued()).unwrap();
+    let decoded = parse(&bytes, &format!("{}::market::InterestAccruedEvent", SCALLOP_PACKAGE)).unwrap();
+    let event = decoded.scallop().unwrap();
+    assert!(matches!(event, ScallopEvent::InterestAccruedEvent(event) if event.borrow_index == 1_050_000_000 && event.interest_rate == percent(3)));
+    // accrual does not change market configuration
+    assert_eq!(event.market_asset(), None);
+}
+
+#[test]
+fn market_history_test(){
+    let mut market = MarketHistory::new();
+    let changed: Vec<usize> = history().iter().map(|batch| market.apply_batch(batch).len()).collect();
+    // proposed change is not in effect
+    assert_eq!(changed, vec![1, 0, 1, 1]);
+    assert!(market.at(SUI, 999).is_none());
+    let listed = market.at(SUI, 1500).unwrap();
+    assert_eq!(listed.checkpoint, 1000);
+    assert_eq!(listed.risk_model, Some(risk_model(60)));
+    let current = market.at(SUI, 1800).unwrap();
+    assert_eq!(current.risk_model, Some(risk_model(70)));
+    assert_eq!(current.interest_model, Some(interest_model()));
+    assert_eq!(current.borrow_limit, None);
+    assert_eq!(market.history(SUI).len(), 3);
+    // limits are changed on top of the models in effect
+    let limited = market.at(SUI, 2000).unwrap();
+    assert_eq!(limited.risk_model, Some(risk_model(70)));
+    assert_eq!(limited.borrow_limit, Some(500_000_000_000));
+    assert_eq!(limited.supply_limit, Some(2_000_000_000_000));
+    let risk = current.asset_risk(9).unwrap();
+    assert!((risk.collateral_factor - 0.7).abs() < 1e-9);
+    assert!((risk.liquidation_factor - 0.8).abs() < 1e-9);
+    assert!((risk.borrow_weight - 1.25).abs() < 1e-9);
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn sqlite_market_test(){
+    use sui_indexer::sink::sqlite::SqliteSink;
+    use sui_indexer::sink::{Sink, Watermark, LIVE_WATERMARK};
+    let sink = SqliteSink::open_in_memory().unwrap();
+    for batch in history() {
+        let watermark = Watermark { name: LIVE_WATERMARK.to_string(), checkpoint: batch.checkpoint };
+        sink.write(std::slice::from_ref(&batch), &watermark).await.unwrap();
+    }
+    // collateral factor in effect at checkpoint
+    let collateral_factor: i64 = sink.with_connection(|connection| {
+        Ok(connection.query_row(
+            "SELECT CAST(collateral_factor AS INTEGER) FROM risk_model_added_events WHERE asset = ?1 AND checkpoint <= 1500 ORDER BY checkpoint DESC LIMIT 1",
+            [SUI], |row| row.get(0),
+        )?)
+    }).unwrap();
+    assert_eq!(collateral_factor as u64, percent(60).value);
+    let proposed: i64 = sink.with_connection(|connection| {
+        Ok(connection.query_row("SELECT CAST(effective_epoches AS INTEGER) FROM risk_model_change_created_events", [], |row| row.get(0))?)
+    }).unwrap();
+    assert_eq!(proposed, 108);
+    let limit: i64 = sink.with_connection(|connection| {
+        Ok(connection.query_row("SELECT CAST(borrow_limit AS INTEGER) FROM borrow_limit_changed_events", [], |row| row.get(0))?)
+    }).unwrap();
+    assert_eq!(limit, 500_000_000_000);
+    let accrued: String = sink.with_connection(|connection| {
+        Ok(connection.query_row("SELECT borrow_index FROM interest_accrued_events", [], |row| row.get(0))?)
+    }).unwrap();
+    assert_eq!(accrued, "1050000000");
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn sqlite_market_replay_test(){
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use sui_indexer::decoder::DecoderRegistry;
+    use sui_indexer::filter::{FilterConfig, PackageFilter};
+    use sui_indexer::health::{AssetRisk, HealthCalculator, RiskConfig};
+    use sui_indexer::obligation::ObligationState;
+    use sui_indexer::pipeline::Pipeline;
+    use sui_indexer::sink::sqlite::SqliteSink;
+    use sui_indexer::sink::{Sink, Watermark, LIVE_WATERMARK};
+    let sink = SqliteSink::open_in_memory().unwrap();
+    let market_pipeline = || {
+        let mut pipeline = Pipeline::new(PackageFilter::new(&FilterConfig::scallop()), DecoderRegistry::scallop());
+        pipeline.market = Some(Arc::new(tokio::sync::Mutex::new(MarketHistory::new())));
+        pipeline
+    };
+    let mut batches = history();
+    let limits = batches.pop().unwrap();
+    let pipeline = market_pipeline();
+    for mut batch in batches {
+        pipeline.track_market(&sink, &mut batch).await.unwrap();
+        let watermark = Watermark { name: LIVE_WATERMARK.to_string(), checkpoint: batch.checkpoint };
+        pipeline.write(&sink, std::slice::from_ref(&batch), &watermark).await.unwrap();
+    }
+    // configuration in effect at checkpoint is replayed from market_config
+    let configs = sink.market_configs(&[SUI.to_string()], 1500).await.unwrap();
+    assert_eq!(configs.len(), 1);
+    assert_eq!(configs[0].checkpoint, 1000);
+    assert_eq!(configs[0].risk_model, Some(risk_model(60)));
+    assert!(sink.market_configs(&[SUI.to_string()], 999).await.unwrap().is_empty());
+    let stored: i64 = sink.with_connection(|connection| {
+        Ok(connection.query_row("SELECT COUNT(*) FROM market_config WHERE asset = ?1", [SUI], |row| row.get(0))?)
+    }).unwrap();
+    assert_eq!(stored, 2);
+
+    // restarted pipeline loads the latest configuration and applies limits on top of risk model 70
+    let restarted = market_pipeline();
+    let mut batch = limits;
+    restarted.track_market(&sink, &mut batch).await.unwrap();
+    assert_eq!(batch.market_configs.len(), 1);
+    assert_eq!(batch.market_configs[0].risk_model, Some(risk_model(70)));
+    assert_eq!(batch.market_configs[0].interest_model, Some(interest_model()));
+    assert_eq!(batch.market_configs[0].borrow_limit, Some(500_000_000_000));
+    let watermark = Watermark { name: LIVE_WATERMARK.to_string(), checkpoint: batch.checkpoint };
+    restarted.write(&sink, std::slice::from_ref(&batch), &watermark).await.unwrap();
+    let configs = sink.market_configs(&[SUI.to_string()], 2000).await.unwrap();
+    assert_eq!(configs[0].supply_limit, Some(2_000_000_000_000));
+
+    // health uses parameters in effect at checkpoint, decimals and prices come from risk config
+    let config = RiskConfig {
+        assets: vec![AssetRisk { asset: SUI.to_string(), decimals: 9, collateral_factor: 0.5, liquidation_factor: 0.5, borrow_weight: 1.0 }],
+        prices: HashMap::from([(SUI.to_string(), 2.0)]),
+    };
+    let obligation = ObligationState {
+        id: "0x1".to_string(),
+        collaterals: BTreeMap::from([(SUI.to_string(), 100_000_000_000)]),
+        debts: BTreeMap::from([(SUI.to_string(), 50_000_000_000)]),
+        checkpoint: 2000,
+    };
+    let market = pipeline.market.as_ref().unwrap().lock().await;
+    let mut calculator = HealthCalculator::new(&config);
+    // 100 SUI collateral at liquidation factor 0.7, 50 SUI debt with borrow weight 1.25
+    calculator.apply_market(&market, 1500);
+    assert!((calculator.health(&obligation).health_factor.unwrap() - 1.12).abs() < 1e-9);
+    calculator.apply_market(&market, 1800);
+    assert!((calculator.health(&obligation).health_factor.unwrap() - 1.28).abs() < 1e-9);
+    // history of restarted pipeline starts with the loaded configuration
+    let market = restarted.market.as_ref().unwrap().lock().await;
+    let mut calculator = HealthCalculator::new(&config);
+    calculator.apply_market(&market, 2000);
+    assert!((calculator.health(&obligation).health_factor.unwrap() - 1.28).abs() < 1e-9);
+}
@@ -1,91 +1,46 @@
-use std::sync::Arc;
-use fred::clients::RedisClient;
-use fred::cmd;
-use fred::interfaces::SetsInterface;
-use fred::prelude::{Blocking, ClientLike, ConnectionConfig, KeysInterface, PerformanceConfig, ReconnectPolicy, RedisConfig, RedisResult, Server};
-use fred::prelude::ServerConfig::Centralized;
-use fred::types::RespVersion;
-use log::{debug, LevelFilter, warn};
-use sui_indexer::events::IndexerData;
-use serde_json::Result;
+use std::collections::HashMap;
+use fred::prelude::*;
+use sui_indexer::decoder::DecoderRegistry;
+use sui_indexer::sink::redis::{RedisOptions, RedisSink};
+use sui_indexer::sink::{Sink, LIVE_WATERMARK};
+use common::{mint_batch, redis_url};
 
+mod common;
+
+/// Needs redis set by `REDIS_URL`, database 14 is flushed.
 #[tokio::test]
 async fn migrate_test(){
-    // env_logger::builder().filter_level(LevelFilter::Debug).init();
-    // let client = Arc::new(RedisClient::new(RedisConfig{
-    //     fail_fast: false,
-    //     blocking: Blocking::Interrupt,
-    //     username: None,
-    //     password: None,
-    //     server: Centralized{
-    //         server: Server {
-    //             host: "localhost".parse().unwrap(),
-    //             port: 6379,
-    //         }
-    //     },
-    //     version: RespVersion::RESP2,
-    //     database: Some(0),
-    // }, Some(PerformanceConfig::default()), Some(ConnectionConfig::default()), Some(ReconnectPolicy::default())));
-    // // let _ = runtime.block_on(async {
-    // //     let _ = client.connect();
-    // //     client.wait_for_connect().await
-    // // });
-    // let _ = client.connect();
-    // client.wait_for_connect().await;
-    // let keys: Vec<String> = client.custom(cmd!("KEYS"), vec!["*"]).await.unwrap();
-    // debug!("got: {}", keys.len());
-    // // let with_id = vec!["BorrowEvent", "BorrowEventV2"];
-    // for (idx, key) in keys.iter().enumerate() {
-    //     if key == "0" || key.starts_with("id") || key.starts_with("events_") {
-    //         continue
-    //     }
-    //     // debug!("inserting data: {} {}", idx, key);
-    //     let raw_value: RedisResult<String> = client.get(key).await;
-    //     if raw_value.is_err() {
-    //         warn!("wrong {}", key );
-    //         continue
-    //     }
-    //     let value = raw_value.unwrap();
-    //     let indexer_data_raw: Result<IndexerData> = serde_json::from_str(&*value);
-    //     if indexer_data_raw.is_err() {
-    //         warn!("wrong {}", key );
-    //         continue
-    //     }
-    //     let indexer_data = indexer_data_raw.unwrap();
-    //     let digest = indexer_data.digest.clone();
-    //     let data = indexer_data.clone();
-    //     let event = data.parse_event();
-    //     let result = serde_json::to_string(&data).unwrap();
-    //     // more events can have same digest ... with index is unique
-    //     let mut digest_modified = format!("{}::{}::{}", data.checkpoint, digest, data.index);
-    //     if event.is_some() {
-    //         let (_, event_name, obligation_id) = event.unwrap();
-    //         // digest_modified.push_str("::");
-    //         // digest_modified.push_str(&event_name);
-    //         if obligation_id.is_some() {
-    //             let id = obligation_id.unwrap();
-    //             let mut id_set = "id_".to_string();
-    //             id_set.push_str(&*id);
-    //             // stores indexer data in id_{obligation_id}
-    //             client.sadd::<String,String,String>(id_set, result).await;
-    //             // stores obligation_id in ids set ...
-    //             client.sadd::<String,String,String>("ids".to_string(), id).await;
-    //             let mut events_set = "events_".to_string();
-    //             events_set.push_str(&*data.type_);
-    //             client.sadd::<String,String,String>(events_set, digest_modified.clone()).await;
-    //             debug!("inserting obligations");
-    //             client.del::<String, String>(key.clone()).await;
-    //             continue;
-    //         }
-    //     }
-    //     // store keys in set
-    //     let mut events_set = "events_".to_string();
-    //     events_set.push_str(&*data.type_);
-    //     // stores digest modified key in events_{event type} query
-    //     client.sadd::<String,String,String>(events_set, digest_modified.clone()).await;
-    //     // stores event data as value with modified digest as key
-    //     client.set::<String, String, String>(digest_modified, result, None, None, false).await;
-    //     debug!("inserting data: {} {} {}", digest, idx, key);
-    //     // client.del::<String, String>(key.clone()).await;
-    // }
-}
\ No newline at end of file
+    let url = match redis_url() {
+        Some(url) => url,
+        None => return,
+    };
+    let options = RedisOptions { url: Some(url), db: Some(14), ..RedisOptions::default() };
+    let sink = RedisSink::connect(&options).await.unwrap();
+    let client = &sink.client;
+    client.flushdb::<()>(false).await.unwrap();
+    // layout written before schema version 2
+    let batch = mint_batch(10964321);
+    let mint = &batch.events[0];
+    let events_set = format!("events_{}", mint.data.type_);
+    client.sadd::<(), _, _>(&events_set, mint.key()).await.unwrap();
+    client.set::<(), _, _>(mint.key(), serde_json::to_string(&mint.data).unwrap(), None, None, false).await.unwrap();
+    let mut obligation_event = mint.data.clone();
+    obligation_event.index = 5;
+    client.sadd::<(), _, _>("id_0xabc", serde_json::to_string(&obligation_event).unwrap()).await.unwrap();
+    client.sadd::<(), _, _>("ids", "0xabc").await.unwrap();
+    let unknown = &batch.events[1];
+    let dead_letter = serde_json::json!({"key": unknown.key(), "error": "unknown", "data": unknown.data});
+    client.sadd::<(), _, _>("dead_letters", dead_letter.to_string()).await.unwrap();
+    client.set::<(), _, _>("0", 10964321, None, None, false).await.unwrap();
+
+    assert_eq!(sink.migrate(&DecoderRegistry::scallop(), true).await.unwrap(), 3);
+    assert_eq!(sink.watermark(LIVE_WATERMARK).await.unwrap(), Some(10964321));
+    let ids: Vec<String> = client.zrange(RedisSink::key(&format!("type:{}", mint.data.type_)), 0, -1, None, false, None, false).await.unwrap();
+    assert_eq!(ids.len(), 2);
+    let event: HashMap<String, String> = client.hgetall(RedisSink::event_key(&mint.key())).await.unwrap();
+    assert_eq!(event["name"], "MintEvent");
+    assert_eq!(client.zcard::<u64, _>(RedisSink::key("dead_letters")).await.unwrap(), 1);
+    assert_eq!(client.exists::<u64, _>(vec!["0", "ids", "id_0xabc", events_set.as_str()]).await.unwrap(), 0);
+    // migration can be repeated
+    assert_eq!(sink.migrate(&DecoderRegistry::scallop(), false).await.unwrap(), 0);
+}
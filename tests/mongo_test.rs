@@ -0,0 +1,60 @@
+#![cfg(feature = "mongodb")]
+use mongodb::bson::{doc, Document};
+use mongodb::Collection;
+use sui_indexer::objects::ObjectSnapshot;
+use sui_indexer::sink::mongo::{MongoSink, EVENTS_COLLECTION, OBJECTS_COLLECTION};
+use sui_indexer::sink::{Sink, Watermark, LIVE_WATERMARK};
+use common::mint_batch;
+
+mod common;
+
+fn snapshot(version: u64) -> ObjectSnapshot {
+    ObjectSnapshot {
+        id: "0x5".to_string(),
+        version,
+        type_: "0x2::coin::Coin<0x2::sui::SUI>".to_string(),
+        owner: "0x6".to_string(),
+        checkpoint: version,
+        digest: format!("digest{}", version),
+        data: vec![version as u8],
+        deleted: false,
+        decoded: Ok(None),
+        obligation: None,
+    }
+}
+
+async fn version(objects: &Collection<Document>) -> i64 {
+    return objects.find_one(doc! {"_id": "0x5"}, None).await.unwrap().unwrap().get_i64("version").unwrap();
+}
+
+/// Needs local mongodb, eg. `docker run -p 27017:27017 mongo:7`, database `indexer_test` is dropped.
+#[tokio::test]
+async fn mongo_object_version_test(){
+    let url = std::env::var("MONGODB_URL").unwrap_or("mongodb://localhost:27017".to_string());
+    let sink = MongoSink::connect(&url, "indexer_test").await.unwrap();
+    sink.database.drop(None).await.unwrap();
+    sink.create_indexes().await.unwrap();
+    let objects = sink.database.collection::<Document>(OBJECTS_COLLECTION);
+    let mut batch = mint_batch(10964321);
+    batch.objects = vec![snapshot(20)];
+    let watermark = Watermark { name: LIVE_WATERMARK.to_string(), checkpoint: 10964321 };
+    sink.write(std::slice::from_ref(&batch), &watermark).await.unwrap();
+    assert_eq!(version(&objects).await, 20);
+    // older version (eg. backfill behind live indexer) does not replace stored one, events are still upserted
+    let mut batch = mint_batch(10964322);
+    batch.objects = vec![snapshot(10)];
+    let watermark = Watermark { name: "backfill_10964322_10964330".to_string(), checkpoint: 10964322 };
+    sink.write(std::slice::from_ref(&batch), &watermark).await.unwrap();
+    assert_eq!(version(&objects).await, 20);
+    assert_eq!(sink.watermark(&watermark.name).await.unwrap(), Some(10964322));
+    assert_eq!(sink.database.collection::<Document>(EVENTS_COLLECTION).count_documents(None, None).await.unwrap(), 4);
+    // the newest of more versions in one batch wins
+    let mut batch = mint_batch(10964323);
+    batch.objects = vec![snapshot(40), snapshot(30)];
+    let watermark = Watermark { name: LIVE_WATERMARK.to_string(), checkpoint: 10964323 };
+    sink.write(std::slice::from_ref(&batch), &watermark).await.unwrap();
+    assert_eq!(version(&objects).await, 40);
+    let object = objects.find_one(doc! {"_id": "0x5"}, None).await.unwrap().unwrap();
+    assert_eq!(object.get_str("digest").unwrap(), "digest40");
+    assert_eq!(objects.count_documents(None, None).await.unwrap(), 1);
+}
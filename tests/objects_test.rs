@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+use move_core_types::language_storage::StructTag;
+use sui_indexer::events::{TypeName, SCALLOP_PACKAGE};
+use sui_indexer::objects::{BalanceBag, Debt, Field, ObjectFilter, ObjectSnapshot, ObligationObject, ScallopObject, Table, WitTable};
+use sui_types::base_types::ObjectID;
+use sui_types::id::UID;
+use sui_types::parse_sui_struct_tag;
+
+const OBLIGATION: &str = "0x8f8bb2a1b9e5d0fb3a5b2f5c16e2a7f9bc3ac1bd10ae2e1b7f3c6f7a6ec11b21";
+const DEBTS_TABLE: &str = "0x0a6b3c9d1f64e1b14d9c3e0f5be25f5e3f8a0bb3b3f4a8b3e5d1d9f2f9a2c7d1";
+const USDC: &str = "5d4b302506645c37ff133b98c4b50a5ae14841659738d6d733d59d0d217a93bf::coin::COIN";
+
+fn uid(id: &str) -> UID {
+    UID::new(ObjectID::from_hex_literal(id).unwrap())
+}
+
+fn tag(type_: &str) -> StructTag {
+    parse_sui_struct_tag(type_).unwrap()
+}
+
+fn table(id: &str) -> WitTable {
+    WitTable { id: uid("0x1"), table: Table { id: uid(id), size: 1 }, keys: vec![], with_keys: false }
+}
+
+fn obligation() -> ObligationObject {
+    ObligationObject {
+        id: uid(OBLIGATION),
+        balances: BalanceBag { id: uid("0x2"), bag: Table { id: uid("0x3"), size: 0 } },
+        debts: table(DEBTS_TABLE),
+        collaterals: table("0x4"),
+        rewards_point: 0,
+        lock_key: None,
+        borrow_locked: false,
+        repay_locked: false,
+        deposit_collateral_locked: false,
+        withdraw_collateral_locked: false,
+        liquidate_locked: false,
+    }
+}
+
+fn debt_type() -> String {
+    format!("0x2::dynamic_field::Field<0x1::type_name::TypeName, {}::obligation_debts::Debt>", SCALLOP_PACKAGE)
+}
+
+#[test]
+fn object_filter_test(){
+    let filter = ObjectFilter::scallop();
+    assert!(filter.matches(&tag(&format!("{}::obligation::Obligation", SCALLOP_PACKAGE))));
+    assert!(filter.matches(&tag(&debt_type())));
+    assert!(!filter.matches(&tag("0x2::dynamic_field::Field<0x1::type_name::TypeName, 0x2::balance::Balance<0x2::sui::SUI>>")));
+    assert!(!filter.matches(&tag("0x2::coin::Coin<0x2::sui::SUI>")));
+    let filter = ObjectFilter::new(&["0x2::coin::Coin".to_string()]).unwrap();
+    assert!(filter.matches(&tag("0x2::coin::Coin<0x2::sui::SUI>")));
+    assert!(ObjectFilter::new(&["0x2::coin".to_string()]).is_err());
+}
+
+#[test]
+fn decode_obligation_objects_test(){
+    let bytes = bcs::to_bytes(&obligation()).unwrap();
+    let decoded = ScallopObject::decode(&tag(&format!("{}::obligation::Obligation", SCALLOP_PACKAGE)), &bytes).unwrap();
+    match decoded {
+        Some(ScallopObject::Obligation(obligation)) => assert_eq!(obligation.debts.table.id.id.bytes.to_string(), DEBTS_TABLE),
+        other => panic!("unexpected {:?}", other),
+    }
+    let field = Field { id: uid("0x5"), name: TypeName { name: USDC.to_string() }, value: Debt { amount: 1500, borrow_index: 1000000000 } };
+    let decoded = ScallopObject::decode(&tag(&debt_type()), &bcs::to_bytes(&field).unwrap()).unwrap();
+    match decoded {
+        Some(ScallopObject::Debt { asset, amount, .. }) => assert_eq!((asset.as_str(), amount), (USDC, 1500)),
+        other => panic!("unexpected {:?}", other),
+    }
+    // other types are stored without decoding, wrong layout is an error
+    assert!(ScallopObject::decode(&tag("0x2::coin::Coin<0x2::sui::SUI>"), &[1, 2, 3]).unwrap().is_none());
+    assert!(ScallopObject::decode(&tag(&debt_type()), &[1, 2, 3]).is_err());
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn sqlite_objects_test(){
+    use sui_indexer::sink::sqlite::SqliteSink;
+    use sui_indexer::sink::{CheckpointBatch, Sink, Watermark, LIVE_WATERMARK};
+    let sink = SqliteSink::open_in_memory().unwrap();
+    let snapshot = |version: u64, amount: u64| {
+        let field = Field { id: uid("0x5"), name: TypeName { name: USDC.to_string() }, value: Debt { amount, borrow_index: 1000000000 } };
+        let data = bcs::to_bytes(&field).unwrap();
+        ObjectSnapshot {
+            id: "0x5".to_string(),
+            version,
+            type_: debt_type(),
+            owner: DEBTS_TABLE.to_string(),
+            checkpoint: version,
+            digest: "digest".to_string(),
+            decoded: ScallopObject::decode(&tag(&debt_type()), &data),
+            data,
+            deleted: false,
+            obligation: Some(OBLIGATION.to_string()),
+        }
+    };
+    for (version, amount) in [(20, 2000), (10, 1000)] {
+        let batch = CheckpointBatch { checkpoint: version, epoch: 300, timestamp_ms: 0, events: vec![], obligations: vec![], objects: vec![snapshot(version, amount)], price_updates: vec![], prices: BTreeMap::new(), market_configs: vec![] };
+        let watermark = Watermark { name: LIVE_WATERMARK.to_string(), checkpoint: version };
+        sink.write(std::slice::from_ref(&batch), &watermark).await.unwrap();
+    }
+    // older version does not replace newer one
+    let (version, amount): (i64, i64) = sink.with_connection(|connection| {
+        Ok(connection.query_row("SELECT version, CAST(amount AS INTEGER) FROM objects WHERE obligation = ?1", [OBLIGATION], |row| Ok((row.get(0)?, row.get(1)?)))?)
+    }).unwrap();
+    assert_eq!((version, amount), (20, 2000));
+}
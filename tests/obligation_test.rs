@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+use sui_indexer::decoder::{DecodedEvent, EventPayload};
+use sui_indexer::events::{BorrowEvent, CollateralDepositEvent, CollateralWithdrawEvent, IndexerData, LiquidateEvent, RepayEvent, ScallopEvent, TypeName};
+use sui_indexer::obligation::ObligationBook;
+use sui_indexer::sink::{CheckpointBatch, IndexedEvent};
+use sui_types::base_types::{ObjectID, SuiAddress};
+use sui_types::id::ID;
+
+const OBLIGATION: &str = "0x8f8bb2a1b9e5d0fb3a5b2f5c16e2a7f9bc3ac1bd10ae2e1b7f3c6f7a6ec11b21";
+const SUI: &str = "0000000000000000000000000000000000000000000000000000000000000002::sui::SUI";
+const USDC: &str = "5d4b302506645c37ff133b98c4b50a5ae14841659738d6d733d59d0d217a93bf::coin::COIN";
+
+fn obligation() -> ID {
+    ID::new(ObjectID::from_hex_literal(OBLIGATION).unwrap())
+}
+
+fn asset(name: &str) -> TypeName {
+    TypeName { name: name.to_string() }
+}
+
+fn batch(checkpoint: u64, events: Vec<ScallopEvent>) -> CheckpointBatch {
+    let events = events.into_iter().enumerate().map(|(index, event)| {
+        let data = IndexerData {
+            digest: format!("digest{}", checkpoint),
+            checkpoint,
+            epoch: 300,
+            data: vec![],
+            index: index as u64,
+            type_: "test".to_string(),
+            sender: SuiAddress::ZERO.to_string(),
+        };
+        let decoded = DecodedEvent { event: EventPayload::Scallop(event), name: "test".to_string(), obligation: Some(obligation().bytes.to_string()) };
+        IndexedEvent { data, decoded: Ok(decoded) }
+    }).collect();
+    CheckpointBatch { checkpoint, epoch: 300, timestamp_ms: 1709390000000, events, obligations: vec![], objects: vec![], price_updates: vec![], prices: BTreeMap::new(), market_configs: vec![] }
+}
+
+fn history() -> Vec<CheckpointBatch> {
+    vec![
+        batch(100, vec![
+            ScallopEvent::CollateralDepositEvent(CollateralDepositEvent { provider: SuiAddress::ZERO, obligation: obligation(), deposit_asset: asset(SUI), deposit_amount: 1000 }),
+            ScallopEvent::BorrowEvent(BorrowEvent { borrower: SuiAddress::ZERO, obligation: obligation(), asset: asset(USDC), amount: 300, time: 1 }),
+        ]),
+        batch(101, vec![
+            ScallopEvent::RepayEvent(RepayEvent { repayer: SuiAddress::ZERO, obligation: obligation(), asset: asset(USDC), amount: 100, time: 2 }),
+            ScallopEvent::CollateralWithdrawEvent(CollateralWithdrawEvent { taker: SuiAddress::ZERO, obligation: obligation(), withdraw_asset: asset(SUI), withdraw_amount: 200 }),
+        ]),
+        batch(102, vec![
+            ScallopEvent::LiquidateEvent(LiquidateEvent {
+                liquidator: SuiAddress::ZERO, obligation: obligation(), debt_type: asset(USDC), collateral_type: asset(SUI),
+                repay_on_behalf: 50, repay_revenue: 5, liq_amount: 120,
+            }),
+        ]),
+    ]
+}
+
+#[test]
+fn obligation_replay_test(){
+    let mut book = ObligationBook::new();
+    for batch in history().iter() {
+        let changed = book.apply_batch(batch);
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].checkpoint, batch.checkpoint);
+    }
+    let id = obligation().bytes.to_string();
+    let state = book.get(&id).unwrap();
+    assert_eq!(state.collaterals.get(SUI), Some(&680));
+    assert_eq!(state.debts.get(USDC), Some(&150));
+    // replayed checkpoint does not change position
+    assert!(book.apply_batch(&history()[2]).is_empty());
+    assert_eq!(book.get(&id).unwrap().debts.get(USDC), Some(&150));
+    // repay with interest clears principal
+    let repay = ScallopEvent::RepayEvent(RepayEvent { repayer: SuiAddress::ZERO, obligation: obligation(), asset: asset(USDC), amount: 160, time: 3 });
+    let changed = book.apply_batch(&batch(103, vec![repay]));
+    assert!(changed[0].debts.is_empty());
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn obligation_sqlite_test(){
+    use sui_indexer::sink::sqlite::SqliteSink;
+    use sui_indexer::sink::{Sink, Watermark, LIVE_WATERMARK};
+    let sink = SqliteSink::open_in_memory().unwrap();
+    let mut book = ObligationBook::new();
+    for mut batch in history() {
+        batch.obligations = book.apply_batch(&batch);
+        let watermark = Watermark { name: LIVE_WATERMARK.to_string(), checkpoint: batch.checkpoint };
+        sink.write(std::slice::from_ref(&batch), &watermark).await.unwrap();
+    }
+    let id = obligation().bytes.to_string();
+    let stored = sink.obligations(&[id.clone(), "0x1".to_string()]).await.unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(&stored[0], book.get(&id).unwrap());
+    assert_eq!(stored[0].checkpoint, 102);
+}
@@ -0,0 +1,84 @@
+#![cfg(feature = "parquet")]
+use std::fs;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use arrow_array::{Array, RecordBatch, UInt64Array};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use sui_indexer::sink::parquet::ParquetSink;
+use sui_indexer::sink::{Sink, Watermark, LIVE_WATERMARK};
+use common::mint_batch;
+
+mod common;
+
+/// Empty export directory of the test.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    return dir;
+}
+
+fn rows(path: &Path) -> Vec<RecordBatch> {
+    let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path).unwrap()).unwrap().build().unwrap();
+    return reader.map(|batch| batch.unwrap()).collect();
+}
+
+#[tokio::test]
+async fn parquet_epoch_roll_test(){
+    let dir = test_dir("parquet_epoch_roll_test");
+    let sink = ParquetSink::open(dir.to_str().unwrap(), 10).unwrap();
+    let watermark = Watermark { name: LIVE_WATERMARK.to_string(), checkpoint: 10964321 };
+    sink.write(&[mint_batch(10964321)], &watermark).await.unwrap();
+    // file is not full yet, so watermark is not stored
+    assert_eq!(sink.watermark(LIVE_WATERMARK).await.unwrap(), None);
+    let epoch_120 = dir.join("mint_events").join("epoch=120");
+    assert!(epoch_120.join("part-10964321.parquet.inprogress").exists());
+
+    // first checkpoint of next epoch finishes files of the previous one
+    let mut next_epoch = mint_batch(10964322);
+    next_epoch.epoch = 121;
+    let watermark = Watermark { name: LIVE_WATERMARK.to_string(), checkpoint: 10964322 };
+    sink.write(&[next_epoch], &watermark).await.unwrap();
+    assert_eq!(sink.watermark(LIVE_WATERMARK).await.unwrap(), Some(10964321));
+    let batches = rows(&epoch_120.join("part-10964321.parquet"));
+    assert_eq!(batches.iter().map(|batch| batch.num_rows()).sum::<usize>(), 1);
+    let amounts = batches[0].column_by_name("deposit_amount").unwrap().as_any().downcast_ref::<UInt64Array>().unwrap();
+    assert_eq!(amounts.value(0), 26000000000);
+    assert!(batches[0].column_by_name("timestamp_ms").is_some());
+    let epoch_121 = dir.join("mint_events").join("epoch=121");
+    assert!(epoch_121.join("part-10964322.parquet.inprogress").exists());
+
+    sink.close().await.unwrap();
+    assert_eq!(sink.watermark(LIVE_WATERMARK).await.unwrap(), Some(10964322));
+    assert_eq!(rows(&epoch_121.join("part-10964322.parquet"))[0].num_rows(), 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn parquet_interrupted_roll_test(){
+    let dir = test_dir("parquet_interrupted_roll_test");
+    let sink = ParquetSink::open(dir.to_str().unwrap(), 1).unwrap();
+    let watermark = Watermark { name: LIVE_WATERMARK.to_string(), checkpoint: 10964321 };
+    sink.write(&[mint_batch(10964321)], &watermark).await.unwrap();
+    let finished = dir.join("mint_events").join("epoch=120").join("part-10964321.parquet");
+    assert!(finished.exists());
+    // crash after the file was closed and recorded in manifest, before it was renamed and watermark stored
+    let in_progress = PathBuf::from(format!("{}.inprogress", finished.to_string_lossy()));
+    fs::rename(&finished, &in_progress).unwrap();
+    fs::write(dir.join("_watermarks.json"), "{}").unwrap();
+    let manifest = serde_json::json!({"files": ["mint_events/epoch=120/part-10964321.parquet.inprogress"], "watermarks": {"live": 10964321}});
+    fs::write(dir.join("_roll.json"), manifest.to_string()).unwrap();
+    // file opened after the roll, its checkpoints are not stored yet
+    let unfinished = dir.join("mint_events").join("epoch=121").join("part-10964322.parquet.inprogress");
+    fs::create_dir_all(unfinished.parent().unwrap()).unwrap();
+    fs::write(&unfinished, "PAR1").unwrap();
+
+    // restart finishes the roll, so checkpoint 10964321 is not exported again, and removes unfinished file
+    let sink = ParquetSink::open(dir.to_str().unwrap(), 10).unwrap();
+    assert_eq!(sink.watermark(LIVE_WATERMARK).await.unwrap(), Some(10964321));
+    assert!(finished.exists());
+    assert!(!in_progress.exists());
+    assert!(!dir.join("_roll.json").exists());
+    assert!(!unfinished.exists());
+    assert_eq!(rows(&finished)[0].num_rows(), 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
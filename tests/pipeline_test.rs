@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use anyhow::{anyhow, Result};
+use futures::{stream, StreamExt};
+use async_trait::async_trait;
+use sui_indexer::decoder::DecoderRegistry;
+use sui_indexer::fetcher::{ArchiveFetcher, MAINNET_ARCHIVE_URL};
+use sui_indexer::filter::{FilterConfig, PackageFilter};
+use sui_indexer::pipeline::Pipeline;
+use sui_indexer::reader::{CheckpointReader, InMemorySource};
+use sui_indexer::sink::{CheckpointBatch, Sink, Watermark, LIVE_WATERMARK};
+
+#[derive(Default)]
+struct MemorySink {
+    events: Mutex<Vec<String>>,
+    checkpoints: Mutex<Vec<u64>>,
+    watermarks: Mutex<HashMap<String, u64>>,
+    /// Writes of this and later checkpoints fail.
+    fail_from: Mutex<Option<u64>>,
+}
+
+#[async_trait]
+impl Sink for MemorySink {
+    async fn write(&self, batch: &[CheckpointBatch], watermark: &Watermark) -> Result<()> {
+        if let Some(fail_from) = *self.fail_from.lock().unwrap() {
+            if watermark.checkpoint >= fail_from {
+                return Err(anyhow!("sink is down"));
+            }
+        }
+        for checkpoint in batch {
+            self.events.lock().unwrap().extend(checkpoint.events.iter().map(|event| event.key()));
+            self.checkpoints.lock().unwrap().push(checkpoint.checkpoint);
+        }
+        self.watermarks.lock().unwrap().insert(watermark.name.clone(), watermark.checkpoint);
+        Ok(())
+    }
+
+    async fn watermark(&self, name: &str) -> Result<Option<u64>> {
+        return Ok(self.watermarks.lock().unwrap().get(name).cloned());
+    }
+}
+
+/// Sink which is down for the next `failures` writes and watermark reads,
+/// records whether checkpoint file still exists at each write.
+struct FlakySink {
+    sink: MemorySink,
+    failures: Mutex<u32>,
+    file: PathBuf,
+    file_exists: Mutex<Vec<bool>>,
+}
+
+impl FlakySink {
+    fn fail(&self) -> Result<()> {
+        let mut failures = self.failures.lock().unwrap();
+        if *failures > 0 {
+            *failures -= 1;
+            return Err(anyhow!("sink is down"));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Sink for FlakySink {
+    async fn write(&self, batch: &[CheckpointBatch], watermark: &Watermark) -> Result<()> {
+        self.file_exists.lock().unwrap().push(self.file.exists());
+        self.fail()?;
+        return self.sink.write(batch, watermark).await;
+    }
+
+    async fn watermark(&self, name: &str) -> Result<Option<u64>> {
+        self.fail()?;
+        return self.sink.watermark(name).await;
+    }
+}
+
+fn pipeline() -> Pipeline {
+    let mut pipeline = Pipeline::new(PackageFilter::new(&FilterConfig::scallop()), DecoderRegistry::scallop());
+    pipeline.exit = true;
+    return pipeline;
+}
+
+#[tokio::test]
+async fn pipeline_empty_source_test(){
+    let sink = MemorySink::default();
+    pipeline().run(&mut InMemorySource::new(vec![]), &sink).await.unwrap();
+    assert_eq!(sink.watermark(LIVE_WATERMARK).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn pipeline_test(){
+    let fetcher = ArchiveFetcher::new(MAINNET_ARCHIVE_URL);
+    let checkpoint = fetcher.fetch(10964321).await.unwrap().unwrap();
+    let sink = MemorySink::default();
+    pipeline().run(&mut InMemorySource::new(vec![checkpoint]), &sink).await.unwrap();
+    assert_eq!(sink.watermark(LIVE_WATERMARK).await.unwrap(), Some(10964321));
+    println!("events: {:?}", sink.events.lock().unwrap());
+}
+
+#[tokio::test]
+async fn backfill_resume_test(){
+    let (from, to) = (10964321, 10964340);
+    let fetcher = ArchiveFetcher::new(MAINNET_ARCHIVE_URL);
+    let checkpoints: Vec<_> = fetcher.stream(from..to + 1).map(|result| result.unwrap()).collect().await;
+    let in_memory = |range: std::ops::Range<u64>| stream::iter(checkpoints.iter().filter(move |(number, _)| range.contains(number)).cloned().map(Ok::<_, anyhow::Error>));
+    let sink = MemorySink::default();
+    let mut pipeline = pipeline();
+    pipeline.max_write_retries = 1;
+    // sink goes down in the middle of backfill
+    *sink.fail_from.lock().unwrap() = Some(10964330);
+    assert!(pipeline.backfill_with(in_memory, from, to, 8, &sink).await.is_err());
+    assert_eq!(sink.watermark("backfill_10964321_10964340").await.unwrap(), Some(10964329));
+    // resumed after the last stored checkpoint
+    *sink.fail_from.lock().unwrap() = None;
+    pipeline.backfill_with(in_memory, from, to, 8, &sink).await.unwrap();
+    // decoded by parallel workers, stored in sequence order without gaps and duplicates
+    assert_eq!(*sink.checkpoints.lock().unwrap(), (from..=to).collect::<Vec<_>>());
+    assert_eq!(sink.watermark("backfill_10964321_10964340").await.unwrap(), Some(to));
+    assert_eq!(sink.watermark(LIVE_WATERMARK).await.unwrap(), None);
+    // finished backfill is not repeated
+    pipeline.backfill_with(in_memory, from, to, 8, &sink).await.unwrap();
+    assert_eq!(sink.checkpoints.lock().unwrap().len(), 20);
+}
+
+#[tokio::test]
+async fn pipeline_write_retry_test(){
+    let dir = std::env::temp_dir().join(format!("pipeline_write_retry_test_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("10964321.chk");
+    let fetcher = ArchiveFetcher::new(MAINNET_ARCHIVE_URL);
+    fs::write(&file, fetcher.fetch_bytes(10964321).await.unwrap().unwrap()).unwrap();
+    let sink = FlakySink { sink: MemorySink::default(), failures: Mutex::new(5), file: file.clone(), file_exists: Mutex::new(vec![]) };
+    let mut pipeline = pipeline();
+    pipeline.max_backoff = Duration::from_millis(200);
+
+    // pipeline stops after limit of retries, checkpoint file is kept for next start
+    pipeline.max_write_retries = 2;
+    let mut source = CheckpointReader { path: dir.clone(), current_checkpoint_number: 10964321 };
+    assert!(pipeline.run(&mut source, &sink).await.is_err());
+    assert_eq!(*sink.file_exists.lock().unwrap(), vec![true; 3]);
+    assert!(file.exists());
+    // next start reads watermark after sink is up again
+    pipeline.max_write_retries = 0;
+    assert_eq!(pipeline.start_checkpoint(&sink).await.unwrap(), 0);
+    assert_eq!(*sink.failures.lock().unwrap(), 0);
+
+    // 0 retries until write succeeds, file is removed only after that
+    *sink.failures.lock().unwrap() = 5;
+    sink.file_exists.lock().unwrap().clear();
+    let mut source = CheckpointReader { path: dir.clone(), current_checkpoint_number: 10964321 };
+    pipeline.run(&mut source, &sink).await.unwrap();
+    assert_eq!(*sink.file_exists.lock().unwrap(), vec![true; 6]);
+    assert!(!file.exists());
+    assert_eq!(sink.watermark(LIVE_WATERMARK).await.unwrap(), Some(10964321));
+    assert_eq!(pipeline.start_checkpoint(&sink).await.unwrap(), 10964322);
+    fs::remove_dir_all(&dir).unwrap();
+}
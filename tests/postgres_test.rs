@@ -0,0 +1,63 @@
+#![cfg(feature = "postgres")]
+use tokio_postgres::NoTls;
+use sui_indexer::sink::postgres::PostgresSink;
+use sui_indexer::decoder::{DecodedEvent, EventPayload};
+use sui_indexer::events::ScallopEvent;
+use sui_indexer::sink::{CheckpointBatch, Sink, Watermark, LIVE_WATERMARK};
+use common::mint_batch;
+
+mod common;
+
+/// Needs postgres set by `POSTGRES_URL`, eg. `docker run -e POSTGRES_PASSWORD=postgres -p 5432:5432 postgres:16`
+/// and `POSTGRES_URL="host=localhost user=postgres password=postgres"`, database `indexer_test` is recreated.
+/// Without `POSTGRES_URL` the test is skipped.
+async fn test_database() -> Option<String> {
+    let url = match std::env::var("POSTGRES_URL") {
+        Ok(url) => url,
+        Err(_) => {
+            println!("POSTGRES_URL is not set, skipping postgres test");
+            return None;
+        }
+    };
+    let (client, connection) = tokio_postgres::connect(&url, NoTls).await.unwrap();
+    tokio::spawn(connection);
+    client.batch_execute("DROP DATABASE IF EXISTS indexer_test WITH (FORCE)").await.unwrap();
+    client.batch_execute("CREATE DATABASE indexer_test").await.unwrap();
+    return Some(format!("{} dbname=indexer_test", url));
+}
+
+/// Checkpoint with mint event of deposit above i64::MAX.
+fn large_mint_batch(checkpoint: u64) -> CheckpointBatch {
+    let mut batch = mint_batch(checkpoint);
+    if let Ok(DecodedEvent { event: EventPayload::Scallop(ScallopEvent::MintEvent(mint)), .. }) = &mut batch.events[0].decoded {
+        mint.deposit_amount = u64::MAX;
+    }
+    return batch;
+}
+
+#[tokio::test]
+async fn postgres_numeric_test(){
+    let url = match test_database().await {
+        Some(url) => url,
+        None => return,
+    };
+    let sink = PostgresSink::connect(&url).await.unwrap();
+    let batch = [large_mint_batch(10964321), large_mint_batch(10964322)];
+    let watermark = Watermark { name: LIVE_WATERMARK.to_string(), checkpoint: 10964322 };
+    sink.write(&batch, &watermark).await.unwrap();
+    assert_eq!(sink.watermark(LIVE_WATERMARK).await.unwrap(), Some(10964322));
+    // migrations are applied only once
+    PostgresSink::connect(&url).await.unwrap();
+
+    let (client, connection) = tokio_postgres::connect(&url, NoTls).await.unwrap();
+    tokio::spawn(connection);
+    // amounts are NUMERIC, so u64 is stored exactly and sums do not overflow
+    let rows = client.query("SELECT deposit_amount::TEXT, mint_amount::TEXT FROM mint_events ORDER BY checkpoint", &[]).await.unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].get::<_, String>(0), u64::MAX.to_string());
+    assert_eq!(rows[0].get::<_, String>(1), "25803699601");
+    let total = client.query_one("SELECT SUM(deposit_amount)::TEXT FROM mint_events", &[]).await.unwrap();
+    assert_eq!(total.get::<_, String>(0), (u64::MAX as u128 * 2).to_string());
+    let errors = client.query("SELECT event_index FROM events WHERE error IS NOT NULL", &[]).await.unwrap();
+    assert_eq!(errors.len(), 2);
+}
@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use sui_indexer::decoder::{DecodedEvent, DecoderRegistry, EventPayload};
+use sui_indexer::events::{BorrowEvent, IndexerData, ScallopEvent, TypeName};
+use sui_indexer::filter::{FilterConfig, PackageFilter};
+use sui_indexer::pipeline::Pipeline;
+use sui_indexer::prices::{Oracle, PriceBook, PricePoint};
+use sui_indexer::sink::{CheckpointBatch, IndexedEvent, Sink, Watermark};
+use sui_types::base_types::{ObjectID, SuiAddress};
+use sui_types::id::ID;
+
+const SUI: &str = "0000000000000000000000000000000000000000000000000000000000000002::sui::SUI";
+const USDC: &str = "5d4b302506645c37ff133b98c4b50a5ae14841659738d6d733d59d0d217a93bf::coin::COIN";
+
+fn point(asset: &str, checkpoint: u64, value: u64) -> PricePoint {
+    PricePoint { asset: asset.to_string(), checkpoint, timestamp_ms: checkpoint * 1000, value, last_updated: checkpoint }
+}
+
+/// Checkpoint with one borrow of USDC.
+fn borrow_batch(checkpoint: u64) -> CheckpointBatch {
+    let obligation = ID::new(ObjectID::from_hex_literal("0x1").unwrap());
+    let event = ScallopEvent::BorrowEvent(BorrowEvent { borrower: SuiAddress::ZERO, obligation, asset: TypeName { name: USDC.to_string() }, amount: 300, time: 1 });
+    let data = IndexerData {
+        digest: format!("digest{}", checkpoint),
+        checkpoint,
+        epoch: 300,
+        data: vec![],
+        index: 0,
+        type_: "test".to_string(),
+        sender: SuiAddress::ZERO.to_string(),
+    };
+    let decoded = DecodedEvent { event: EventPayload::Scallop(event), name: "BorrowEvent".to_string(), obligation: Some(obligation.bytes.to_string()) };
+    let events = vec![IndexedEvent { data, decoded: Ok(decoded) }];
+    CheckpointBatch { checkpoint, epoch: 300, timestamp_ms: 1709390000000, events, obligations: vec![], objects: vec![], price_updates: vec![], prices: BTreeMap::new(), market_configs: vec![] }
+}
+
+#[test]
+fn price_book_test(){
+    let mut book = PriceBook::new();
+    book.update(&[point(SUI, 100, 1_250_000_000), point(USDC, 100, 1_000_000_000)]);
+    book.update(&[point(SUI, 101, 1_300_000_000)]);
+    assert_eq!(book.get(SUI).unwrap().usd(), 1.3);
+    let assets = vec![SUI.to_string(), USDC.to_string(), "0x2::coin::X".to_string()];
+    assert_eq!(book.missing(&assets), vec!["0x2::coin::X".to_string()]);
+    assert_eq!(book.prices(&assets).len(), 2);
+    // asset without stored price is not looked up again, newer update is kept
+    book.insert_loaded(&assets[1..], vec![point(USDC, 90, 990_000_000)]);
+    assert!(book.missing(&assets).is_empty());
+    assert_eq!(book.get(USDC).unwrap().value, 1_000_000_000);
+}
+
+#[test]
+fn event_prices_test(){
+    let mut batch = borrow_batch(100);
+    let event = &batch.events[0];
+    assert!(event.json(&batch).get("prices").is_none());
+    batch.prices.insert(USDC.to_string(), 1_000_000_000);
+    batch.prices.insert(SUI.to_string(), 1_250_000_000);
+    let event = &batch.events[0];
+    // only assets of the event
+    assert_eq!(event.prices(&batch).len(), 1);
+    assert_eq!(event.json(&batch)["prices"][USDC], 1_000_000_000);
+}
+
+/// Sink which fails to load prices `failures` times.
+struct FlakySink {
+    failures: AtomicU32,
+}
+
+#[async_trait]
+impl Sink for FlakySink {
+    async fn write(&self, _batch: &[CheckpointBatch], _watermark: &Watermark) -> Result<()> {
+        Ok(())
+    }
+
+    async fn watermark(&self, _name: &str) -> Result<Option<u64>> {
+        Ok(None)
+    }
+
+    async fn latest_prices(&self, assets: &[String], checkpoint: u64) -> Result<Vec<PricePoint>> {
+        if self.failures.load(Ordering::SeqCst) > 0 {
+            self.failures.fetch_sub(1, Ordering::SeqCst);
+            return Err(anyhow!("connection lost"));
+        }
+        Ok(assets.iter().map(|asset| point(asset, checkpoint - 1, 1_000_000_000)).collect())
+    }
+}
+
+fn oracle_pipeline(max_retries: u32) -> Pipeline {
+    let mut pipeline = Pipeline::new(PackageFilter::new(&FilterConfig::scallop()), DecoderRegistry::scallop());
+    pipeline.oracle = Some(Oracle::new("0x1").unwrap());
+    pipeline.max_write_retries = max_retries;
+    return pipeline;
+}
+
+#[tokio::test]
+async fn track_prices_retry_test(){
+    // failed read is retried like write
+    let sink = FlakySink { failures: AtomicU32::new(2) };
+    let mut batch = borrow_batch(100);
+    oracle_pipeline(3).track_prices(&sink, &mut batch).await.unwrap();
+    assert_eq!(batch.prices.get(USDC), Some(&1_000_000_000));
+    // and gives up after max retries
+    let sink = FlakySink { failures: AtomicU32::new(2) };
+    let mut batch = borrow_batch(100);
+    assert!(oracle_pipeline(1).track_prices(&sink, &mut batch).await.is_err());
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn sqlite_prices_test(){
+    use sui_indexer::sink::sqlite::SqliteSink;
+    use sui_indexer::sink::LIVE_WATERMARK;
+    let sink = SqliteSink::open_in_memory().unwrap();
+    for (checkpoint, value) in [(100, 990_000_000), (110, 1_000_000_000), (120, 1_010_000_000)] {
+        let batch = CheckpointBatch { checkpoint, epoch: 300, timestamp_ms: 0, events: vec![], obligations: vec![], objects: vec![], price_updates: vec![point(USDC, checkpoint, value)], prices: BTreeMap::new(), market_configs: vec![] };
+        let watermark = Watermark { name: LIVE_WATERMARK.to_string(), checkpoint };
+        // replayed checkpoint does not fail
+        sink.write(std::slice::from_ref(&batch), &watermark).await.unwrap();
+        sink.write(std::slice::from_ref(&batch), &watermark).await.unwrap();
+    }
+    let latest = sink.latest_prices(&[USDC.to_string(), SUI.to_string()], 115).await.unwrap();
+    assert_eq!(latest, vec![point(USDC, 110, 1_000_000_000)]);
+    // u64 above i64::MAX is read back exactly
+    let batch = CheckpointBatch { checkpoint: 90, epoch: 300, timestamp_ms: 0, events: vec![], obligations: vec![], objects: vec![], price_updates: vec![point(SUI, 90, u64::MAX)], prices: BTreeMap::new(), market_configs: vec![] };
+    sink.write(std::slice::from_ref(&batch), &Watermark { name: "prices".to_string(), checkpoint: 90 }).await.unwrap();
+    assert_eq!(sink.latest_prices(&[SUI.to_string()], 115).await.unwrap(), vec![point(SUI, 90, u64::MAX)]);
+    // restarted pipeline loads price in effect from sink
+    let mut pipeline = Pipeline::new(PackageFilter::new(&FilterConfig::scallop()), DecoderRegistry::scallop());
+    pipeline.oracle = Some(Oracle::new("0x1").unwrap());
+    let mut batch = borrow_batch(121);
+    pipeline.track_prices(&sink, &mut batch).await.unwrap();
+    assert_eq!(batch.prices.get(USDC), Some(&1_010_000_000));
+    let watermark = Watermark { name: LIVE_WATERMARK.to_string(), checkpoint: 121 };
+    sink.write(std::slice::from_ref(&batch), &watermark).await.unwrap();
+    let prices: String = sink.with_connection(|connection| {
+        Ok(connection.query_row("SELECT prices FROM events WHERE checkpoint = 121", [], |row| row.get(0))?)
+    }).unwrap();
+    assert_eq!(prices, format!("{{\"{}\":1010000000}}", USDC));
+}
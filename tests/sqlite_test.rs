@@ -0,0 +1,48 @@
+#![cfg(feature = "sqlite")]
+use sui_indexer::decoder::DecoderRegistry;
+use sui_indexer::fetcher::{ArchiveFetcher, MAINNET_ARCHIVE_URL};
+use sui_indexer::filter::{FilterConfig, PackageFilter};
+use sui_indexer::pipeline::Pipeline;
+use sui_indexer::reader::InMemorySource;
+use sui_indexer::sink::sqlite::SqliteSink;
+use sui_indexer::sink::{Sink, Watermark, LIVE_WATERMARK};
+use common::mint_batch;
+
+mod common;
+
+fn count(sink: &SqliteSink, sql: &str) -> i64 {
+    return sink.with_connection(|connection| Ok(connection.query_row(sql, [], |row| row.get(0))?)).unwrap();
+}
+
+#[tokio::test]
+async fn sqlite_transaction_test(){
+    let sink = SqliteSink::open_in_memory().unwrap();
+    let watermark = Watermark { name: LIVE_WATERMARK.to_string(), checkpoint: 10964321 };
+    sink.write(&[mint_batch(10964321)], &watermark).await.unwrap();
+    // insert of mint event fails after its row in events, whole checkpoint and watermark are rolled back
+    sink.with_connection(|connection| Ok(connection.execute_batch("ALTER TABLE mint_events RENAME TO mint_events_moved")?)).unwrap();
+    let watermark = Watermark { name: LIVE_WATERMARK.to_string(), checkpoint: 10964322 };
+    assert!(sink.write(&[mint_batch(10964322)], &watermark).await.is_err());
+    assert_eq!(sink.watermark(LIVE_WATERMARK).await.unwrap(), Some(10964321));
+    assert_eq!(count(&sink, "SELECT COUNT(*) FROM events"), 2);
+    sink.with_connection(|connection| Ok(connection.execute_batch("ALTER TABLE mint_events_moved RENAME TO mint_events")?)).unwrap();
+    // retried write stores the checkpoint once, replay of stored one is skipped
+    sink.write(&[mint_batch(10964322)], &watermark).await.unwrap();
+    sink.write(&[mint_batch(10964322)], &watermark).await.unwrap();
+    assert_eq!(sink.watermark(LIVE_WATERMARK).await.unwrap(), Some(10964322));
+    assert_eq!(count(&sink, "SELECT COUNT(*) FROM events"), 4);
+    assert_eq!(count(&sink, "SELECT COUNT(*) FROM events WHERE error IS NOT NULL"), 2);
+    assert_eq!(count(&sink, "SELECT COUNT(*) FROM mint_events WHERE deposit_amount = '26000000000'"), 2);
+}
+
+#[tokio::test]
+async fn sqlite_pipeline_test(){
+    let fetcher = ArchiveFetcher::new(MAINNET_ARCHIVE_URL);
+    let checkpoint = fetcher.fetch(10964321).await.unwrap().unwrap();
+    let sink = SqliteSink::open_in_memory().unwrap();
+    let mut pipeline = Pipeline::new(PackageFilter::new(&FilterConfig::scallop()), DecoderRegistry::scallop());
+    pipeline.exit = true;
+    pipeline.run(&mut InMemorySource::new(vec![checkpoint]), &sink).await.unwrap();
+    assert_eq!(sink.watermark(LIVE_WATERMARK).await.unwrap(), Some(10964321));
+    println!("events: {}", count(&sink, "SELECT COUNT(*) FROM events"));
+}
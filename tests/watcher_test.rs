@@ -0,0 +1,80 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use sui_indexer::fetcher::{ArchiveFetcher, MAINNET_ARCHIVE_URL};
+use sui_indexer::reader::{CheckpointReader, CheckpointSource};
+
+const FIRST: u64 = 10964321;
+
+/// Empty directory for checkpoint files of the test.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    return dir;
+}
+
+/// Checkpoint files `FIRST..FIRST + count` as stored by full node (and archive).
+async fn checkpoint_files(count: u64) -> Vec<Vec<u8>> {
+    let fetcher = ArchiveFetcher::new(MAINNET_ARCHIVE_URL);
+    let mut files = vec![];
+    for number in FIRST..FIRST + count {
+        files.push(fetcher.fetch_bytes(number).await.unwrap().unwrap().to_vec());
+    }
+    return files;
+}
+
+#[tokio::test]
+async fn watcher_test(){
+    let dir = test_dir("watcher_test");
+    let files = checkpoint_files(3).await;
+    // written before watcher started, found by the first rescan
+    fs::write(dir.join(format!("{}.chk", FIRST)), &files[0]).unwrap();
+    let reader = CheckpointReader{ path: dir.clone(), current_checkpoint_number: FIRST };
+    let mut watcher = reader.watch(Duration::from_secs(3600)).unwrap();
+    let batch = watcher.next_batch().await.unwrap();
+    assert_eq!(batch.iter().map(|checkpoint| checkpoint.checkpoint_summary.sequence_number).collect::<Vec<_>>(), vec![FIRST]);
+
+    // written in place (close after write) and written into temporary file and renamed
+    fs::write(dir.join(format!("{}.chk", FIRST + 1)), &files[1]).unwrap();
+    let temporary = dir.join(format!("tmp_{}", FIRST + 2));
+    fs::write(&temporary, &files[2]).unwrap();
+    fs::rename(&temporary, dir.join(format!("{}.chk", FIRST + 2))).unwrap();
+    let mut numbers = vec![];
+    for _ in 0..10 {
+        let batch = watcher.next_batch().await.unwrap();
+        numbers = batch.iter().map(|checkpoint| checkpoint.checkpoint_summary.sequence_number).collect();
+        if numbers.len() == 3 {
+            break;
+        }
+    }
+    // next rescan is in an hour, new files come from notifications, each of them once
+    assert_eq!(numbers, vec![FIRST, FIRST + 1, FIRST + 2]);
+
+    for number in FIRST..FIRST + 3 {
+        watcher.commit(number).await.unwrap();
+    }
+    assert!(watcher.next_batch().await.unwrap().is_empty());
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn watcher_rescan_test(){
+    let dir = test_dir("watcher_rescan_test");
+    let files = checkpoint_files(2).await;
+    // files written before watcher started, the one below start checkpoint is skipped
+    fs::write(dir.join(format!("{}.chk", FIRST - 1)), &files[0]).unwrap();
+    fs::write(dir.join(format!("{}.chk", FIRST + 1)), &files[1]).unwrap();
+    fs::write(dir.join(format!("{}.chk", FIRST)), &files[0]).unwrap();
+    let reader = CheckpointReader{ path: dir.clone(), current_checkpoint_number: FIRST };
+    let mut watcher = reader.watch(Duration::from_millis(200)).unwrap();
+    let batch = watcher.next_batch().await.unwrap();
+    assert_eq!(batch.iter().map(|checkpoint| checkpoint.checkpoint_summary.sequence_number).collect::<Vec<_>>(), vec![FIRST, FIRST + 1]);
+    watcher.commit(FIRST).await.unwrap();
+    // following rescan does not return committed checkpoint again
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let batch = watcher.next_batch().await.unwrap();
+    assert_eq!(batch.iter().map(|checkpoint| checkpoint.checkpoint_summary.sequence_number).collect::<Vec<_>>(), vec![FIRST + 1]);
+    fs::remove_dir_all(&dir).unwrap();
+}